@@ -0,0 +1,188 @@
+/*
+ * Compares decoding sample by sample through a cursor with the bulk conversion of `Sample::read_slice`.
+ * The per sample baseline is a copy of the cursor decoder `Sample::read` used before the bulk conversion.
+ * Run with `cargo bench --bench decode`.
+ */
+
+use std::hint::black_box;
+use std::io::Cursor;
+use std::time::{Duration, Instant};
+
+use wavers::{Sample, WaveDecodeError, WaveFile, WaveFormat};
+use wavers::util::{g711, math};
+use wavers::util::exts::Endian;
+use wavers::util::exts::read::SizedDataRead;
+
+const SAMPLE_RATE: u32 = 96000;
+const CHANNELS: u16 = 2;
+const SECONDS: u32 = 60;
+const RUNS: u32 = 5;
+
+/**
+ * The per sample decoder of `Sample::read` before it was implemented on top of `Sample::read_slice`.
+ */
+trait CursorSample: Sized {
+    fn read_cursor(cursor: &mut Cursor<Vec<u8>>, format: WaveFormat, bits: u16) -> Result<Self, WaveDecodeError>;
+}
+
+impl CursorSample for i16 {
+
+    fn read_cursor(cursor: &mut Cursor<Vec<u8>>, format: WaveFormat, bits: u16) -> Result<Self, WaveDecodeError> {
+        match format {
+            WaveFormat::Pcm => match bits {
+                8 => Ok( math::map_u8_to_i16(cursor.read_u8()?)  ),
+                16 => Ok( cursor.read_i16(Endian::Little)? ),
+
+                /* Unsupported cases */
+                b if b > 16 => Err(WaveDecodeError::IncompatibleSampleType { format, bits, sample_type: std::any::type_name::<Self>() }),
+                _ => Err(WaveDecodeError::UnsupportedBitDepth { format, bits })
+            },
+            WaveFormat::IeeeFloat => Err(WaveDecodeError::IncompatibleSampleType { format, bits, sample_type: std::any::type_name::<Self>() }),
+            WaveFormat::Alaw => match bits {
+                8 => Ok( g711::expand_alaw(cursor.read_u8()?) ),
+                _ => Err(WaveDecodeError::UnsupportedBitDepth { format, bits })
+            },
+            WaveFormat::Mulaw => match bits {
+                8 => Ok( g711::expand_mulaw(cursor.read_u8()?) ),
+                _ => Err(WaveDecodeError::UnsupportedBitDepth { format, bits })
+            },
+            _ => Err(WaveDecodeError::UnsupportedFormat(format)),
+        }
+    }
+}
+impl CursorSample for i32 {
+
+    fn read_cursor(cursor: &mut Cursor<Vec<u8>>, format: WaveFormat, bits: u16) -> Result<Self, WaveDecodeError> {
+        match format {
+            WaveFormat::Pcm => match bits {
+                8 => Ok( math::map_u8_to_i32(cursor.read_u8()?)  ),
+                16 => Ok( math::map_i16_to_i32(cursor.read_i16(Endian::Little)?) ),
+                24 => Ok( cursor.read_i24(Endian::Little)? ),
+                32 => Ok( cursor.read_i32(Endian::Little)? ),
+
+                /* Unsupported cases */
+                b if b > 32 => Err(WaveDecodeError::IncompatibleSampleType { format, bits, sample_type: std::any::type_name::<Self>() }),
+                _ => Err(WaveDecodeError::UnsupportedBitDepth { format, bits })
+            },
+            WaveFormat::IeeeFloat => Err(WaveDecodeError::IncompatibleSampleType { format, bits, sample_type: std::any::type_name::<Self>() }),
+            WaveFormat::Alaw => match bits {
+                8 => Ok( math::map_i16_to_i32(g711::expand_alaw(cursor.read_u8()?)) ),
+                _ => Err(WaveDecodeError::UnsupportedBitDepth { format, bits })
+            },
+            WaveFormat::Mulaw => match bits {
+                8 => Ok( math::map_i16_to_i32(g711::expand_mulaw(cursor.read_u8()?)) ),
+                _ => Err(WaveDecodeError::UnsupportedBitDepth { format, bits })
+            },
+            _ => Err(WaveDecodeError::UnsupportedFormat(format)),
+        }
+    }
+}
+impl CursorSample for f32 {
+
+    fn read_cursor(cursor: &mut Cursor<Vec<u8>>, format: WaveFormat, bits: u16) -> Result<Self, WaveDecodeError> {
+        match format {
+            WaveFormat::Pcm => match bits {
+                8 => Ok( math::map_u8_to_f32(cursor.read_u8()?)  ),
+                16 => Ok( math::map_i16_to_f32(cursor.read_i16(Endian::Little)?) ),
+                24 => Ok( math::map_i24_to_f32(cursor.read_i24(Endian::Little)?) ),
+                32 => Ok( math::map_i32_to_f32(cursor.read_i32(Endian::Little)?) ),
+                64 => Ok( math::map_i64_to_f32(cursor.read_i64(Endian::Little)?) ),
+
+                /* Unsupported cases */
+                b if b > 64 => Err(WaveDecodeError::IncompatibleSampleType { format, bits, sample_type: std::any::type_name::<Self>() }),
+                _ => Err(WaveDecodeError::UnsupportedBitDepth { format, bits })
+            },
+            WaveFormat::IeeeFloat => match bits {
+                32 => Ok( cursor.read_f32(Endian::Little)? ),
+                64 => Ok( cursor.read_f64(Endian::Little)? as f32 ),
+                _ => Err(WaveDecodeError::UnsupportedBitDepth { format, bits })
+            },
+            WaveFormat::Alaw => match bits {
+                8 => Ok( math::map_i16_to_f32(g711::expand_alaw(cursor.read_u8()?)) ),
+                _ => Err(WaveDecodeError::UnsupportedBitDepth { format, bits })
+            },
+            WaveFormat::Mulaw => match bits {
+                8 => Ok( math::map_i16_to_f32(g711::expand_mulaw(cursor.read_u8()?)) ),
+                _ => Err(WaveDecodeError::UnsupportedBitDepth { format, bits })
+            },
+            _ => Err(WaveDecodeError::UnsupportedFormat(format)),
+        }
+    }
+}
+impl CursorSample for f64 {
+
+    fn read_cursor(cursor: &mut Cursor<Vec<u8>>, format: WaveFormat, bits: u16) -> Result<Self, WaveDecodeError> {
+        match format {
+            WaveFormat::Pcm => match bits {
+                8 => Ok( math::map_u8_to_f64(cursor.read_u8()?)  ),
+                16 => Ok( math::map_i16_to_f64(cursor.read_i16(Endian::Little)?) ),
+                24 => Ok( math::map_i24_to_f64(cursor.read_i24(Endian::Little)?) ),
+                32 => Ok( math::map_i32_to_f64(cursor.read_i32(Endian::Little)?) ),
+                64 => Ok( math::map_i64_to_f64(cursor.read_i64(Endian::Little)?) ),
+
+                /* Unsupported cases */
+                b if b > 64 => Err(WaveDecodeError::IncompatibleSampleType { format, bits, sample_type: std::any::type_name::<Self>() }),
+                _ => Err(WaveDecodeError::UnsupportedBitDepth { format, bits })
+            },
+            WaveFormat::IeeeFloat => match bits {
+                32 => Ok( cursor.read_f32(Endian::Little)? as f64 ),
+                64 => Ok( cursor.read_f64(Endian::Little)? ),
+                _ => Err(WaveDecodeError::UnsupportedBitDepth { format, bits })
+            },
+            WaveFormat::Alaw => match bits {
+                8 => Ok( math::map_i16_to_f64(g711::expand_alaw(cursor.read_u8()?)) ),
+                _ => Err(WaveDecodeError::UnsupportedBitDepth { format, bits })
+            },
+            WaveFormat::Mulaw => match bits {
+                8 => Ok( math::map_i16_to_f64(g711::expand_mulaw(cursor.read_u8()?)) ),
+                _ => Err(WaveDecodeError::UnsupportedBitDepth { format, bits })
+            },
+            _ => Err(WaveDecodeError::UnsupportedFormat(format)),
+        }
+    }
+}
+
+fn per_sample<S: CursorSample>(wave_file: &WaveFile, data: &[u8]) -> Vec<S> {
+    let mut cursor = Cursor::new(data.to_vec());
+    let mut samples: Vec<S> = Vec::new();
+    while cursor.position() < data.len() as u64 {
+        samples.push(S::read_cursor(&mut cursor, wave_file.format, wave_file.bits_per_sample).unwrap());
+    }
+    samples
+}
+
+fn measure<F: FnMut() -> usize>(mut run: F) -> Duration {
+    let mut best = Duration::MAX;
+    for _ in 0..RUNS {
+        let start = Instant::now();
+        black_box(run());
+        best = best.min(start.elapsed());
+    }
+    best
+}
+
+fn bench<S: Sample + CursorSample>(format: WaveFormat, bits: u16) {
+    let len = (SAMPLE_RATE * SECONDS) as usize * CHANNELS as usize;
+    let source: Vec<f64> = (0..len).map(|x| ((x as f64) * 0.001).sin() * 0.9).collect();
+    let wave_file = WaveFile::from_samples(&source, CHANNELS, SAMPLE_RATE, format, bits).unwrap();
+
+    let mut data: Vec<u8> = Vec::new();
+    for sample in &source {
+        sample.write(&mut data, format, bits).unwrap();
+    }
+
+    let cursor = measure(|| per_sample::<S>(&wave_file, &data).len());
+    let bulk = measure(|| wave_file.samples::<S>().unwrap().len());
+    println!("{:>2}-bit {:<10} -> {:<3}  per sample {:>9.2?}  bulk {:>9.2?}  speedup {:>5.1}x",
+        bits, format.stringify(), std::any::type_name::<S>(), cursor, bulk, cursor.as_secs_f64() / bulk.as_secs_f64());
+}
+
+fn main() {
+    println!("Decoding {} s of {} Hz stereo, best of {} runs", SECONDS, SAMPLE_RATE, RUNS);
+    bench::<i16>(WaveFormat::Pcm, 16);
+    bench::<f32>(WaveFormat::Pcm, 16);
+    bench::<f32>(WaveFormat::Pcm, 24);
+    bench::<i32>(WaveFormat::Pcm, 24);
+    bench::<f32>(WaveFormat::IeeeFloat, 32);
+    bench::<f64>(WaveFormat::Pcm, 32);
+}
@@ -0,0 +1,39 @@
+use std::env;
+use std::fs::{File, OpenOptions};
+use std::process::exit;
+
+use wavers::repair::{repair, repair_copy};
+
+/**
+ * Repairs the header of a WAVE file whose recording was never finalized.
+ *
+ * repair <file>            rewrites the header of <file> in place
+ * repair <input> <output>  writes a repaired copy of <input> to <output>
+ */
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    let result = match args.len() {
+        2 => OpenOptions::new().read(true).write(true).open(&args[1])
+            .map_err(|e| e.into())
+            .and_then(|mut file| repair(&mut file))
+            .map(|report| match report.changed() {
+                true => println!("RIFF size {} -> {}, data size {} -> {}, removed {} B",
+                    report.old_riff_size, report.new_riff_size, report.old_data_size, report.new_data_size, report.removed_bytes),
+                false => println!("Nothing to repair"),
+            }),
+        3 => File::open(&args[1])
+            .and_then(|source| File::create(&args[2]).map(|sink| (source, sink)))
+            .map_err(|e| e.into())
+            .and_then(|(source, sink)| repair_copy(source, sink))
+            .map(|warnings| warnings.iter().for_each(|x| println!("{}", x))),
+        _ => {
+            eprintln!("Usage: {} <file> | <input> <output>", args[0]);
+            exit(2);
+        },
+    };
+
+    if let Err(e) = result {
+        eprintln!("{}", e);
+        exit(1);
+    }
+}
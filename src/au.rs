@@ -0,0 +1,163 @@
+use crate::{DecodeWarning, WaveDecodeError, WaveFile, WaveFormat};
+use crate::raw::{RawPcmReader, RawPcmSpec, RawPcmWriter};
+use crate::util::exts::{Endian, read::*, write::*};
+use crate::util::text::{decode_text, TextEncoding};
+
+use std::io::{Error as IoError, ErrorKind, Write};
+
+const AU_MAGIC: u32 = 0x2e736e64;       /* ".snd", stored big-endian */
+const AU_HEADER_SIZE: u32 = 24;
+const AU_UNKNOWN_SIZE: u32 = 0xffffffff;
+
+const AU_ENCODING_MULAW: u32 = 1;
+const AU_ENCODING_PCM8: u32 = 2;
+const AU_ENCODING_PCM16: u32 = 3;
+const AU_ENCODING_PCM24: u32 = 4;
+const AU_ENCODING_PCM32: u32 = 5;
+const AU_ENCODING_FLOAT: u32 = 6;
+const AU_ENCODING_DOUBLE: u32 = 7;
+const AU_ENCODING_ALAW: u32 = 27;
+
+/**
+ * Reads Sun/NeXT AU files. The samples are converted to the layout of WAVE, so the result
+ * can be used like any decoded WAVE file. The annotation is available as comment.
+ */
+pub struct AuReader<R>
+where R: SizedDataRead {
+    source: R,
+}
+
+impl<R> AuReader<R>
+where R: SizedDataRead {
+
+    pub fn new(source: R) -> AuReader<R> {
+        AuReader::<R> { source }
+    }
+
+    pub fn decode(&mut self) -> Result<WaveFile, WaveDecodeError> {
+        let mut buf: Vec<u8> = Vec::new();
+        self.source.read_to_end(&mut buf)?;
+        decode_bytes(&buf)
+    }
+}
+
+/**
+ * Decodes an AU file that is completely held by `buf`.
+ */
+pub fn decode_bytes(buf: &[u8]) -> Result<WaveFile, WaveDecodeError> {
+    if buf.len() < (AU_HEADER_SIZE as usize) {
+        return Err(WaveDecodeError::InvalidHeader);
+    }
+
+    let mut header = &buf[..(AU_HEADER_SIZE as usize)];
+    let magic = header.read_u32(Endian::Big)?;
+    let data_offset = header.read_u32(Endian::Big)?;
+    let data_size = header.read_u32(Endian::Big)?;
+    let encoding = header.read_u32(Endian::Big)?;
+    let sample_rate = header.read_u32(Endian::Big)?;
+    let channels = header.read_u32(Endian::Big)?;
+    if magic != AU_MAGIC || data_offset < AU_HEADER_SIZE || (data_offset as usize) > buf.len() {
+        return Err(WaveDecodeError::InvalidHeader);
+    }
+
+    let (format, bits) = match encoding {
+        AU_ENCODING_MULAW => (WaveFormat::Mulaw, 8),
+        AU_ENCODING_PCM8 => (WaveFormat::Pcm, 8),
+        AU_ENCODING_PCM16 => (WaveFormat::Pcm, 16),
+        AU_ENCODING_PCM24 => (WaveFormat::Pcm, 24),
+        AU_ENCODING_PCM32 => (WaveFormat::Pcm, 32),
+        AU_ENCODING_FLOAT => (WaveFormat::IeeeFloat, 32),
+        AU_ENCODING_DOUBLE => (WaveFormat::IeeeFloat, 64),
+        AU_ENCODING_ALAW => (WaveFormat::Alaw, 8),
+        x => return Err(WaveDecodeError::Unsupported(format!("Unsupported AU encoding {}", x))),
+    };
+    let channels = match u16::try_from(channels) {
+        Ok(x) => x,
+        Err(_) => return Err(WaveDecodeError::Unsupported(format!("Unsupported number of channels {}", channels))),
+    };
+
+    // The size is optional, streamed files state 0xffffffff and the data extends to the end.
+    let available = buf.len() - (data_offset as usize);
+    let mut warnings: Vec<DecodeWarning> = Vec::new();
+    let len = match data_size {
+        AU_UNKNOWN_SIZE => available,
+        x if (x as usize) > available => {
+            warnings.push(DecodeWarning::DataClamped { offset: data_offset as u64, declared: x, actual: available as u32 });
+            available
+        },
+        x => x as usize,
+    };
+
+    let data = &buf[(data_offset as usize)..(data_offset as usize + len)];
+    let spec = RawPcmSpec { endian: Endian::Big, signed: true, ..RawPcmSpec::new(format, channels, sample_rate, bits) };
+    let mut wave_file = RawPcmReader::new(data, spec).decode()?;
+
+    let annotation = decode_text(&buf[(AU_HEADER_SIZE as usize)..(data_offset as usize)], TextEncoding::Latin1);
+    if !annotation.is_empty() {
+        wave_file.metadata.comments = Some(annotation);
+    }
+    for warning in wave_file.warnings.iter_mut() {
+        if let Some(offset) = warning.offset_mut() {
+            *offset += data_offset as u64;
+        }
+    }
+    warnings.append(&mut wave_file.warnings);
+    wave_file.warnings = warnings;
+
+    Ok(wave_file)
+}
+
+/**
+ * Writes a `WaveFile` as Sun/NeXT AU file, the comment of the metadata is written as annotation.
+ */
+pub struct AuWriter<W>
+where W: Write {
+    sink: W,
+}
+
+impl<W> AuWriter<W>
+where W: Write {
+
+    pub fn new(sink: W) -> AuWriter<W> {
+        AuWriter::<W> { sink }
+    }
+
+    pub fn into_inner(self) -> W {
+        self.sink
+    }
+
+    pub fn encode(&mut self, wave_file: &WaveFile) -> Result<(), IoError> {
+        let encoding = match (wave_file.format, wave_file.bits_per_sample) {
+            (WaveFormat::Mulaw, 8) => AU_ENCODING_MULAW,
+            (WaveFormat::Pcm, 8) => AU_ENCODING_PCM8,
+            (WaveFormat::Pcm, 16) => AU_ENCODING_PCM16,
+            (WaveFormat::Pcm, 24) => AU_ENCODING_PCM24,
+            (WaveFormat::Pcm, 32) => AU_ENCODING_PCM32,
+            (WaveFormat::IeeeFloat, 32) => AU_ENCODING_FLOAT,
+            (WaveFormat::IeeeFloat, 64) => AU_ENCODING_DOUBLE,
+            (WaveFormat::Alaw, 8) => AU_ENCODING_ALAW,
+            (format, bits) => return Err(IoError::new(ErrorKind::InvalidInput, format!(
+                "{}-bit {} cannot be stored in AU files", bits, format.stringify()))),
+        };
+        let data_size = match u32::try_from(wave_file.sample_data.len()) {
+            Ok(x) if x != AU_UNKNOWN_SIZE => x,
+            _ => return Err(IoError::new(ErrorKind::InvalidInput, "AU file exceeds 4 GiB")),
+        };
+
+        // The annotation is NUL-terminated and padded to a multiple of 8 bytes, at least 4 bytes are required.
+        let mut annotation = wave_file.metadata.comments.as_deref().unwrap_or("").as_bytes().to_vec();
+        annotation.push(0);
+        annotation.resize(annotation.len().max(4).next_multiple_of(8), 0);
+
+        self.sink.write_u32(AU_MAGIC, Endian::Big)?;
+        self.sink.write_u32(AU_HEADER_SIZE + annotation.len() as u32, Endian::Big)?;
+        self.sink.write_u32(data_size, Endian::Big)?;
+        self.sink.write_u32(encoding, Endian::Big)?;
+        self.sink.write_u32(wave_file.sample_rate, Endian::Big)?;
+        self.sink.write_u32(wave_file.channels as u32, Endian::Big)?;
+        self.sink.write_all(&annotation)?;
+
+        let spec = RawPcmSpec { endian: Endian::Big, signed: true, ..RawPcmSpec::from_wave_file(wave_file) };
+        RawPcmWriter::new(&mut self.sink, spec).encode(wave_file)
+    }
+}
@@ -0,0 +1,287 @@
+use crate::{DecodeWarning, FourCC, WaveDecodeError, WaveFile, WaveFormat};
+use crate::raw::{RawPcmReader, RawPcmSpec};
+use crate::util::exts::{Endian, read::*};
+use crate::util::text::{decode_text, TextEncoding};
+
+const CAF_MAGIC: u32 = 0x63616666;      /* "caff", stored big-endian */
+
+const CAF_FORMAT_LPCM: &[u8; 4] = b"lpcm";
+const CAF_FORMAT_ULAW: &[u8; 4] = b"ulaw";
+const CAF_FORMAT_ALAW: &[u8; 4] = b"alaw";
+const CAF_FLAG_FLOAT: u32 = 0x1;
+const CAF_FLAG_LITTLE_ENDIAN: u32 = 0x2;
+const CAF_LAYOUT_USE_BITMAP: u32 = 0x10000;         /* kCAFChannelLayoutTag_UseChannelBitmap */
+
+/* Keys of the info chunk and the INFO fields they are mapped to */
+const CAF_INFO_KEYS: &[(&str, &[u8; 4])] = &[
+    ("title", b"INAM"), ("artist", b"IART"), ("album", b"IPRD"), ("track number", b"ITRK"),
+    ("year", b"ICRD"), ("recorded date", b"ICRD"), ("genre", b"IGNR"), ("comments", b"ICMT"),
+    ("copyright", b"ICOP"), ("encoding application", b"ISFT"), ("keywords", b"IKEY"),
+];
+
+/**
+ * Audio stream description of the `desc` chunk.
+ */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AudioDescription {
+    pub sample_rate: f64,
+    pub format_id: FourCC,
+    pub format_flags: u32,
+    pub bytes_per_packet: u32,
+    pub frames_per_packet: u32,
+    pub channels_per_frame: u32,
+    pub bits_per_channel: u32,
+}
+
+/**
+ * Header of the `pakt` chunk, the packet sizes themselves are not decoded.
+ */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PacketTable {
+    pub packets: i64,
+    pub valid_frames: i64,
+    pub priming_frames: i32,
+    pub remainder_frames: i32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ChannelDescription {
+    pub label: u32,
+    pub flags: u32,
+    pub coordinates: [f32; 3],
+}
+
+/**
+ * Channel layout of the `chan` chunk.
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub struct ChannelLayout {
+    pub tag: u32,
+    pub bitmap: u32,                /* Same bit assignment as the channel mask of WAVE */
+    pub descriptions: Vec<ChannelDescription>,
+}
+
+/**
+ * A decoded CAF file. The samples and the mapped metadata are available as `WaveFile`,
+ * everything specific to CAF is kept alongside.
+ */
+#[derive(Debug)]
+pub struct CafFile {
+    pub wave_file: WaveFile,
+    pub description: AudioDescription,
+    pub edit_count: u32,
+    pub packet_table: Option<PacketTable>,
+    pub channel_layout: Option<ChannelLayout>,
+    pub info: Vec<(String, String)>,
+}
+
+/**
+ * Reads Apple Core Audio Format files holding linear PCM, A-law or µ-law.
+ */
+pub struct CafReader<R>
+where R: SizedDataRead {
+    source: R,
+}
+
+impl<R> CafReader<R>
+where R: SizedDataRead {
+
+    pub fn new(source: R) -> CafReader<R> {
+        CafReader::<R> { source }
+    }
+
+    pub fn decode(&mut self) -> Result<CafFile, WaveDecodeError> {
+        let mut buf: Vec<u8> = Vec::new();
+        self.source.read_to_end(&mut buf)?;
+        decode_bytes(&buf)
+    }
+}
+
+/**
+ * Decodes a CAF file that is completely held by `buf`.
+ */
+pub fn decode_bytes(buf: &[u8]) -> Result<CafFile, WaveDecodeError> {
+    let mut cursor: &[u8] = buf;
+    if buf.len() < 8 || cursor.read_u32(Endian::Big)? != CAF_MAGIC || cursor.read_u16(Endian::Big)? != 1 {
+        return Err(WaveDecodeError::InvalidHeader);
+    }
+
+    let mut description: Option<AudioDescription> = None;
+    let mut data: Option<(u64, &[u8])> = None;
+    let mut edit_count: u32 = 0;
+    let mut packet_table: Option<PacketTable> = None;
+    let mut channel_layout: Option<ChannelLayout> = None;
+    let mut info: Vec<(String, String)> = Vec::new();
+    let mut warnings: Vec<DecodeWarning> = Vec::new();
+
+    let mut pos: usize = 8;
+    while pos + 12 <= buf.len() {
+        let mut header = &buf[pos..(pos + 12)];
+        let id = FourCC(header.read_u32(Endian::Big)?.to_be_bytes());
+        let size = header.read_i64(Endian::Big)?;
+        let start = pos + 12;
+        let available = (buf.len() - start) as u64;
+
+        // Only the data chunk may state -1, it then extends to the end of the file.
+        let size = match size {
+            -1 if &id.0 == b"data" => available,
+            x if x < 0 => return Err(WaveDecodeError::InvalidChunk { id, offset: pos as u64, message: format!("Invalid chunk size {}", x) }),
+            x if (x as u64) > available => {
+                warnings.push(DecodeWarning::TruncatedChunk { id, offset: pos as u64 });
+                available
+            },
+            x => x as u64,
+        };
+        let payload = &buf[start..(start + size as usize)];
+
+        let result = match &id.0 {
+            b"desc" => read_desc_chunk(payload).map(|x| description = Some(x)),
+            b"data" if payload.len() >= 4 => {
+                edit_count = u32::from_be_bytes([ payload[0], payload[1], payload[2], payload[3] ]);
+                data = Some(((start + 4) as u64, &payload[4..]));
+                Ok(())
+            },
+            b"pakt" => read_pakt_chunk(payload).map(|x| packet_table = Some(x)),
+            b"chan" => read_chan_chunk(payload).map(|x| channel_layout = Some(x)),
+            b"info" => read_info_chunk(payload).map(|x| info = x),
+            _ => Ok(()),
+        };
+        result.map_err(|e| match e {
+            WaveDecodeError::Io(_) => WaveDecodeError::InvalidChunk { id, offset: pos as u64, message: String::from("Chunk is too short") },
+            e => e,
+        })?;
+
+        pos = start + size as usize;
+    }
+    if pos < buf.len() {
+        warnings.push(DecodeWarning::TrailingBytes { offset: pos as u64, len: (buf.len() - pos) as u64 });
+    }
+
+    let description = match description {
+        Some(x) => x,
+        None => return Err(WaveDecodeError::MissingChunk(FourCC(*b"desc"))),
+    };
+    let (data_offset, samples) = match data {
+        Some(x) => x,
+        None => return Err(WaveDecodeError::MissingChunk(FourCC(*b"data"))),
+    };
+
+    let mut wave_file = RawPcmReader::new(samples, spec_of(&description)?).decode()?;
+    for warning in wave_file.warnings.iter_mut() {
+        if let Some(offset) = warning.offset_mut() {
+            *offset += data_offset;
+        }
+    }
+    warnings.append(&mut wave_file.warnings);
+    wave_file.warnings = warnings;
+
+    if let Some(layout) = &channel_layout {
+        if layout.tag == CAF_LAYOUT_USE_BITMAP {
+            wave_file.channel_mask = Some(layout.bitmap);
+        }
+    }
+    for (key, value) in &info {
+        let id = CAF_INFO_KEYS.iter().find(|(x, _)| x.eq_ignore_ascii_case(key)).map(|(_, x)| FourCC(**x));
+        if let Some(field) = id.and_then(|x| wave_file.metadata.field_mut(x)) {
+            if field.is_none() {
+                *field = Some(value.clone());
+            }
+        }
+    }
+
+    Ok(CafFile { wave_file, description, edit_count, packet_table, channel_layout, info })
+}
+
+/**
+ * Maps the stream description onto the sample layout of WAVE.
+ */
+fn spec_of(description: &AudioDescription) -> Result<RawPcmSpec, WaveDecodeError> {
+    let bits = match u16::try_from(description.bits_per_channel) {
+        Ok(x) => x,
+        Err(_) => return Err(WaveDecodeError::Unsupported(format!("Unsupported bit depth {}", description.bits_per_channel))),
+    };
+    let float = description.format_flags & CAF_FLAG_FLOAT != 0;
+    let format = match (&description.format_id.0, float) {
+        (CAF_FORMAT_LPCM, false) => WaveFormat::Pcm,
+        (CAF_FORMAT_LPCM, true) => WaveFormat::IeeeFloat,
+        (CAF_FORMAT_ULAW, _) => WaveFormat::Mulaw,
+        (CAF_FORMAT_ALAW, _) => WaveFormat::Alaw,
+        _ => return Err(WaveDecodeError::Unsupported(format!("Unsupported CAF format '{}'", description.format_id))),
+    };
+    let (format, bits) = match format {
+        WaveFormat::Mulaw | WaveFormat::Alaw => (format, 8),
+        x => (x, bits),
+    };
+
+    // Samples must be packed, i.e. one packet holds exactly one frame.
+    let channels = match u16::try_from(description.channels_per_frame) {
+        Ok(x) => x,
+        Err(_) => return Err(WaveDecodeError::Unsupported(format!("Unsupported number of channels {}", description.channels_per_frame))),
+    };
+    let frame_size = (channels as u32).checked_mul(bits as u32).map(|x| x / 8);
+    if description.frames_per_packet != 1 || frame_size != Some(description.bytes_per_packet) || channels == 0 {
+        return Err(WaveDecodeError::Unsupported(String::from("Only packed CAF samples with one frame per packet are supported")));
+    }
+
+    let endian = match description.format_flags & CAF_FLAG_LITTLE_ENDIAN {
+        0 => Endian::Big,
+        _ => Endian::Little,
+    };
+    Ok(RawPcmSpec { endian, signed: true, ..RawPcmSpec::new(format, channels, description.sample_rate.round() as u32, bits) })
+}
+
+fn read_desc_chunk(mut payload: &[u8]) -> Result<AudioDescription, WaveDecodeError> {
+    Ok(AudioDescription {
+        sample_rate: payload.read_f64(Endian::Big)?,
+        format_id: FourCC(payload.read_u32(Endian::Big)?.to_be_bytes()),
+        format_flags: payload.read_u32(Endian::Big)?,
+        bytes_per_packet: payload.read_u32(Endian::Big)?,
+        frames_per_packet: payload.read_u32(Endian::Big)?,
+        channels_per_frame: payload.read_u32(Endian::Big)?,
+        bits_per_channel: payload.read_u32(Endian::Big)?,
+    })
+}
+
+fn read_pakt_chunk(mut payload: &[u8]) -> Result<PacketTable, WaveDecodeError> {
+    Ok(PacketTable {
+        packets: payload.read_i64(Endian::Big)?,
+        valid_frames: payload.read_i64(Endian::Big)?,
+        priming_frames: payload.read_i32(Endian::Big)?,
+        remainder_frames: payload.read_i32(Endian::Big)?,
+    })
+}
+
+fn read_chan_chunk(mut payload: &[u8]) -> Result<ChannelLayout, WaveDecodeError> {
+    let tag = payload.read_u32(Endian::Big)?;
+    let bitmap = payload.read_u32(Endian::Big)?;
+    let count = payload.read_u32(Endian::Big)?;
+
+    let mut descriptions: Vec<ChannelDescription> = Vec::new();
+    for _ in 0..count {
+        descriptions.push(ChannelDescription {
+            label: payload.read_u32(Endian::Big)?,
+            flags: payload.read_u32(Endian::Big)?,
+            coordinates: [ payload.read_f32(Endian::Big)?, payload.read_f32(Endian::Big)?, payload.read_f32(Endian::Big)? ],
+        });
+    }
+
+    Ok(ChannelLayout { tag, bitmap, descriptions })
+}
+
+/**
+ * Reads the key-value pairs of the info chunk, both are NUL-terminated UTF-8 strings.
+ */
+fn read_info_chunk(mut payload: &[u8]) -> Result<Vec<(String, String)>, WaveDecodeError> {
+    let count = payload.read_u32(Endian::Big)?;
+    let mut strings = payload.split(|x| *x == 0).map(|x| decode_text(x, TextEncoding::Latin1));
+
+    let mut entries: Vec<(String, String)> = Vec::new();
+    for _ in 0..count {
+        match (strings.next(), strings.next()) {
+            (Some(key), Some(value)) => entries.push((key, value)),
+            _ => break,
+        };
+    }
+
+    Ok(entries)
+}
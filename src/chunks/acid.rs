@@ -0,0 +1,131 @@
+use std::io::Error as IoError;
+
+use crate::WaveDecodeError;
+use crate::read::CHUNK_ACID;
+use crate::util::exts::{Endian, read::*, write::*};
+
+const ACID_SIZE: u32 = 24;
+
+pub const ACID_FLAG_ONE_SHOT: u32 = 0x01;           /* File is a one-shot instead of a loop */
+pub const ACID_FLAG_ROOT_NOTE: u32 = 0x02;          /* Root note is valid */
+pub const ACID_FLAG_STRETCH: u32 = 0x04;            /* Time stretching is enabled */
+pub const ACID_FLAG_DISK_BASED: u32 = 0x08;         /* File should be streamed from disk instead of RAM */
+pub const ACID_FLAG_HIGH_OCTAVE: u32 = 0x10;        /* ACIDizer high octave setting */
+
+/**
+ * Contents of the `acid` chunk written by ACID and most other loop-based DAWs. It describes
+ * tempo, length in beats and key of a loop.
+ */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AcidChunk {
+    pub flags: u32,
+    pub root_note: u16,             /* MIDI note number, only valid with ACID_FLAG_ROOT_NOTE */
+    pub reserved1: u16,
+    pub reserved2: f32,
+    pub num_beats: u32,
+    pub meter_denominator: u16,
+    pub meter_numerator: u16,
+    pub tempo: f32,                 /* Tempo in beats per minute */
+}
+
+impl AcidChunk {
+
+    pub fn new() -> AcidChunk {
+        AcidChunk {
+            flags: ACID_FLAG_STRETCH,
+            root_note: 60,
+            reserved1: 0x8000,
+            reserved2: 0.0,
+            num_beats: 0,
+            meter_denominator: 4,
+            meter_numerator: 4,
+            tempo: 120.0,
+        }
+    }
+
+    #[inline(always)]
+    pub fn is_one_shot(&self) -> bool {
+        self.flags & ACID_FLAG_ONE_SHOT != 0
+    }
+
+    #[inline(always)]
+    pub fn is_stretched(&self) -> bool {
+        self.flags & ACID_FLAG_STRETCH != 0
+    }
+
+    #[inline(always)]
+    pub fn is_disk_based(&self) -> bool {
+        self.flags & ACID_FLAG_DISK_BASED != 0
+    }
+
+    /**
+     * Returns the root note if the flags mark it as valid.
+     */
+    pub fn root_note(&self) -> Option<u16> {
+        match self.flags & ACID_FLAG_ROOT_NOTE != 0 {
+            true => Some(self.root_note),
+            false => None,
+        }
+    }
+
+    pub fn set_one_shot(&mut self, one_shot: bool) {
+        self.set_flag(ACID_FLAG_ONE_SHOT, one_shot);
+    }
+
+    pub fn set_root_note(&mut self, note: Option<u16>) {
+        if let Some(x) = note {
+            self.root_note = x;
+        }
+        self.set_flag(ACID_FLAG_ROOT_NOTE, note.is_some());
+    }
+
+    fn set_flag(&mut self, flag: u32, set: bool) {
+        match set {
+            true => self.flags |= flag,
+            false => self.flags &= !flag,
+        }
+    }
+
+    /**
+     * Reads the chunk payload, `size` is the size stated in the chunk header.
+     */
+    pub fn read<R: SizedDataRead>(source: &mut R, size: u32) -> Result<AcidChunk, WaveDecodeError> {
+        if size < ACID_SIZE {
+            return Err(WaveDecodeError::invalid_chunk(CHUNK_ACID, format!("Unexpected acid chunk length: {}", size)));
+        }
+
+        Ok(AcidChunk {
+            flags: source.read_u32(Endian::Little)?,
+            root_note: source.read_u16(Endian::Little)?,
+            reserved1: source.read_u16(Endian::Little)?,
+            reserved2: source.read_f32(Endian::Little)?,
+            num_beats: source.read_u32(Endian::Little)?,
+            meter_denominator: source.read_u16(Endian::Little)?,
+            meter_numerator: source.read_u16(Endian::Little)?,
+            tempo: source.read_f32(Endian::Little)?,
+        })
+    }
+
+    /**
+     * Writes the complete chunk including chunk header.
+     */
+    pub fn write<W: SizedDataWrite>(&self, sink: &mut W) -> Result<(), IoError> {
+        sink.write_u32(CHUNK_ACID, Endian::Little)?;
+        sink.write_u32(ACID_SIZE, Endian::Little)?;
+        sink.write_u32(self.flags, Endian::Little)?;
+        sink.write_u16(self.root_note, Endian::Little)?;
+        sink.write_u16(self.reserved1, Endian::Little)?;
+        sink.write_f32(self.reserved2, Endian::Little)?;
+        sink.write_u32(self.num_beats, Endian::Little)?;
+        sink.write_u16(self.meter_denominator, Endian::Little)?;
+        sink.write_u16(self.meter_numerator, Endian::Little)?;
+        sink.write_f32(self.tempo, Endian::Little)
+    }
+}
+
+impl Default for AcidChunk {
+
+    fn default() -> AcidChunk {
+        AcidChunk::new()
+    }
+}
@@ -0,0 +1,132 @@
+use std::io::Error as IoError;
+
+use crate::WaveDecodeError;
+use crate::read::CHUNK_BEXT;
+use crate::util::exts::{Endian, read::*, write::*};
+use crate::util::text::TextEncoding;
+
+/* Size of the fixed part of the chunk, the coding history follows */
+const BEXT_SIZE: u32 = 602;
+
+/**
+ * Contents of the broadcast audio extension chunk (`bext`) defined in EBU Tech 3285.
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub struct BroadcastExtension {
+    pub description: String,                /* max. 256 characters */
+    pub originator: String,                 /* max. 32 characters */
+    pub originator_reference: String,       /* max. 32 characters */
+    pub origination_date: String,           /* yyyy-mm-dd */
+    pub origination_time: String,           /* hh:mm:ss */
+    pub time_reference: u64,                /* First sample count since midnight */
+    pub version: u16,
+    pub umid: [u8; 64],                     /* SMPTE UMID, only valid since version 1 */
+    pub loudness_value: i16,                /* Integrated loudness in LUFS * 100, only valid since version 2 */
+    pub loudness_range: i16,                /* Loudness range in LU * 100 */
+    pub max_true_peak_level: i16,           /* Maximum true peak level in dBTP * 100 */
+    pub max_momentary_loudness: i16,        /* Highest momentary loudness in LUFS * 100 */
+    pub max_short_term_loudness: i16,       /* Highest short-term loudness in LUFS * 100 */
+    pub coding_history: String,
+}
+
+impl BroadcastExtension {
+
+    pub fn new() -> BroadcastExtension {
+        BroadcastExtension {
+            description: String::new(),
+            originator: String::new(),
+            originator_reference: String::new(),
+            origination_date: String::new(),
+            origination_time: String::new(),
+            time_reference: 0,
+            version: 2,
+            umid: [0; 64],
+            loudness_value: 0,
+            loudness_range: 0,
+            max_true_peak_level: 0,
+            max_momentary_loudness: 0,
+            max_short_term_loudness: 0,
+            coding_history: String::new(),
+        }
+    }
+
+    /**
+     * Reads the chunk payload, `size` is the size stated in the chunk header. Text that is not
+     * valid UTF-8 (the specification demands ASCII) is decoded with `fallback`.
+     */
+    pub fn read<R: SizedDataRead>(source: &mut R, size: u32, fallback: TextEncoding) -> Result<BroadcastExtension, WaveDecodeError> {
+        if size < BEXT_SIZE {
+            return Err(WaveDecodeError::invalid_chunk(CHUNK_BEXT, format!("Unexpected bext chunk length: {}", size)));
+        }
+
+        let mut bext = BroadcastExtension::new();
+        bext.description = source.read_string(256, fallback)?;
+        bext.originator = source.read_string(32, fallback)?;
+        bext.originator_reference = source.read_string(32, fallback)?;
+        bext.origination_date = source.read_string(10, fallback)?;
+        bext.origination_time = source.read_string(8, fallback)?;
+        bext.time_reference = source.read_u64(Endian::Little)?;
+        bext.version = source.read_u16(Endian::Little)?;
+        source.read_exact(&mut bext.umid)?;
+        bext.loudness_value = source.read_i16(Endian::Little)?;
+        bext.loudness_range = source.read_i16(Endian::Little)?;
+        bext.max_true_peak_level = source.read_i16(Endian::Little)?;
+        bext.max_momentary_loudness = source.read_i16(Endian::Little)?;
+        bext.max_short_term_loudness = source.read_i16(Endian::Little)?;
+        source.read_bytes(180)?;        // reserved
+        bext.coding_history = source.read_string(size - BEXT_SIZE, fallback)?;
+
+        Ok(bext)
+    }
+
+    /**
+     * Writes the complete chunk including chunk header and pad byte. Text that exceeds
+     * the size of its field is truncated.
+     */
+    pub fn write<W: SizedDataWrite>(&self, sink: &mut W) -> Result<(), IoError> {
+        let history = self.coding_history.as_bytes();
+        let size = BEXT_SIZE + history.len() as u32;
+
+        sink.write_u32(CHUNK_BEXT, Endian::Little)?;
+        sink.write_u32(size, Endian::Little)?;
+        write_fixed_text(sink, &self.description, 256)?;
+        write_fixed_text(sink, &self.originator, 32)?;
+        write_fixed_text(sink, &self.originator_reference, 32)?;
+        write_fixed_text(sink, &self.origination_date, 10)?;
+        write_fixed_text(sink, &self.origination_time, 8)?;
+        sink.write_u64(self.time_reference, Endian::Little)?;
+        sink.write_u16(self.version, Endian::Little)?;
+        sink.write_all(&self.umid)?;
+        sink.write_i16(self.loudness_value, Endian::Little)?;
+        sink.write_i16(self.loudness_range, Endian::Little)?;
+        sink.write_i16(self.max_true_peak_level, Endian::Little)?;
+        sink.write_i16(self.max_momentary_loudness, Endian::Little)?;
+        sink.write_i16(self.max_short_term_loudness, Endian::Little)?;
+        sink.write_all(&[0; 180])?;
+        sink.write_all(history)?;
+        if size % 2 == 1 {
+            sink.write_u8(0)?;
+        }
+        Ok(())
+    }
+}
+
+impl Default for BroadcastExtension {
+
+    fn default() -> BroadcastExtension {
+        BroadcastExtension::new()
+    }
+}
+
+/**
+ * Writes `text` into a NUL-padded field of `len` bytes, truncated at a character boundary.
+ */
+fn write_fixed_text<W: SizedDataWrite>(sink: &mut W, text: &str, len: usize) -> Result<(), IoError> {
+    let mut end = text.len().min(len);
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+
+    sink.write_all(&text.as_bytes()[..end])?;
+    sink.write_all(&vec![0; len - end])
+}
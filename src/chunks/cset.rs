@@ -0,0 +1,61 @@
+use std::io::Error as IoError;
+
+use crate::WaveDecodeError;
+use crate::read::CHUNK_CSET;
+use crate::util::exts::{Endian, read::*, write::*};
+use crate::util::text::TextEncoding;
+
+const CSET_SIZE: u32 = 8;
+
+/**
+ * Contents of the `CSET` chunk, which defines the character set and language of the text
+ * stored in the file.
+ */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CharacterSet {
+    pub code_page: u16,             /* Windows codepage, 0 means the default codepage */
+    pub country_code: u16,
+    pub language: u16,
+    pub dialect: u16,
+}
+
+impl CharacterSet {
+
+    /**
+     * Returns the text encoding declared by the codepage, `None` for the default codepage.
+     */
+    pub fn encoding(&self) -> Option<TextEncoding> {
+        match self.code_page {
+            0 => None,
+            x => Some(TextEncoding::from_code_page(x)),
+        }
+    }
+
+    /**
+     * Reads the chunk payload, `size` is the size stated in the chunk header.
+     */
+    pub fn read<R: SizedDataRead>(source: &mut R, size: u32) -> Result<CharacterSet, WaveDecodeError> {
+        if size < CSET_SIZE {
+            return Err(WaveDecodeError::invalid_chunk(CHUNK_CSET, format!("Unexpected CSET chunk length: {}", size)));
+        }
+
+        Ok(CharacterSet {
+            code_page: source.read_u16(Endian::Little)?,
+            country_code: source.read_u16(Endian::Little)?,
+            language: source.read_u16(Endian::Little)?,
+            dialect: source.read_u16(Endian::Little)?,
+        })
+    }
+
+    /**
+     * Writes the complete chunk including chunk header.
+     */
+    pub fn write<W: SizedDataWrite>(&self, sink: &mut W) -> Result<(), IoError> {
+        sink.write_u32(CHUNK_CSET, Endian::Little)?;
+        sink.write_u32(CSET_SIZE, Endian::Little)?;
+        sink.write_u16(self.code_page, Endian::Little)?;
+        sink.write_u16(self.country_code, Endian::Little)?;
+        sink.write_u16(self.language, Endian::Little)?;
+        sink.write_u16(self.dialect, Endian::Little)
+    }
+}
@@ -0,0 +1,80 @@
+use std::io::Error as IoError;
+
+use crate::{FourCC, WaveDecodeError};
+use crate::read::CHUNK_CUE_;
+use crate::util::exts::{Endian, read::*, write::*};
+
+/* Size of a single cue point */
+const CUE_POINT_SIZE: u32 = 24;
+
+/**
+ * A cue point of the `cue ` chunk, which marks a position in the sample data.
+ */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CuePoint {
+    pub id: u32,                    /* Unique id, referenced by labels in an associated data list */
+    pub position: u32,              /* Sample frame position for playlists */
+    pub data_chunk_id: FourCC,      /* Usually `data` */
+    pub chunk_start: u32,
+    pub block_start: u32,
+    pub sample_offset: u32,         /* Sample frame of the cue point within the data chunk */
+}
+
+impl CuePoint {
+
+    /**
+     * Creates a cue point at the given sample frame of the data chunk.
+     */
+    pub fn new(id: u32, sample_offset: u32) -> CuePoint {
+        CuePoint {
+            id,
+            position: sample_offset,
+            data_chunk_id: FourCC(*b"data"),
+            chunk_start: 0,
+            block_start: 0,
+            sample_offset,
+        }
+    }
+}
+
+/**
+ * Reads the payload of a `cue ` chunk, `size` is the size stated in the chunk header.
+ */
+pub fn read_cue_chunk<R: SizedDataRead>(source: &mut R, size: u32) -> Result<Vec<CuePoint>, WaveDecodeError> {
+    let count = source.read_u32(Endian::Little)?;
+    if (count as u64) * (CUE_POINT_SIZE as u64) + 4 > (size as u64) {
+        return Err(WaveDecodeError::invalid_chunk(CHUNK_CUE_, format!("Number of cue points ({}) exceeds cue chunk", count)));
+    }
+
+    let mut points: Vec<CuePoint> = Vec::with_capacity(count as usize);
+    for _ in 0..count {
+        points.push(CuePoint {
+            id: source.read_u32(Endian::Little)?,
+            position: source.read_u32(Endian::Little)?,
+            data_chunk_id: FourCC::from_u32(source.read_u32(Endian::Little)?),
+            chunk_start: source.read_u32(Endian::Little)?,
+            block_start: source.read_u32(Endian::Little)?,
+            sample_offset: source.read_u32(Endian::Little)?,
+        });
+    }
+
+    Ok(points)
+}
+
+/**
+ * Writes a complete `cue ` chunk including chunk header.
+ */
+pub fn write_cue_chunk<W: SizedDataWrite>(sink: &mut W, points: &[CuePoint]) -> Result<(), IoError> {
+    sink.write_u32(CHUNK_CUE_, Endian::Little)?;
+    sink.write_u32(4 + CUE_POINT_SIZE * (points.len() as u32), Endian::Little)?;
+    sink.write_u32(points.len() as u32, Endian::Little)?;
+    for point in points {
+        sink.write_u32(point.id, Endian::Little)?;
+        sink.write_u32(point.position, Endian::Little)?;
+        sink.write_u32(point.data_chunk_id.as_u32(), Endian::Little)?;
+        sink.write_u32(point.chunk_start, Endian::Little)?;
+        sink.write_u32(point.block_start, Endian::Little)?;
+        sink.write_u32(point.sample_offset, Endian::Little)?;
+    }
+    Ok(())
+}
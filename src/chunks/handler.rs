@@ -0,0 +1,42 @@
+use std::any::Any;
+
+use crate::{FourCC, WaveDecodeError};
+
+/**
+ * Parser for chunks that are not interpreted by this library, e.g. proprietary vendor chunks.
+ * Handlers are registered with `WaveReader::register_handler` and are consulted for every
+ * unknown chunk with a matching id. The parsed value can be retrieved with `WaveFile::get_chunk`.
+ */
+pub trait ChunkHandler {
+    type Output: Any + Send + Sync;
+
+    /**
+     * Id of the chunks this handler parses.
+     */
+    fn id(&self) -> FourCC;
+
+    /**
+     * Parses the payload of the chunk, without chunk header and pad byte.
+     */
+    fn parse(&self, data: &[u8]) -> Result<Self::Output, WaveDecodeError>;
+}
+
+/**
+ * Object safe version of `ChunkHandler`, so handlers with different outputs can be stored together.
+ */
+pub(crate) trait AnyChunkHandler {
+
+    fn id(&self) -> FourCC;
+    fn parse(&self, data: &[u8]) -> Result<Box<dyn Any + Send + Sync>, WaveDecodeError>;
+}
+
+impl<H: ChunkHandler> AnyChunkHandler for H {
+
+    fn id(&self) -> FourCC {
+        ChunkHandler::id(self)
+    }
+
+    fn parse(&self, data: &[u8]) -> Result<Box<dyn Any + Send + Sync>, WaveDecodeError> {
+        Ok( Box::new(ChunkHandler::parse(self, data)?) )
+    }
+}
@@ -0,0 +1,92 @@
+use std::io::Error as IoError;
+
+use crate::{AudioMetadata, FourCC, WaveDecodeError};
+use crate::read::{CHUNK_LIST, CHUNK_LIST_SUB_INFO};
+use crate::util::exts::{Endian, read::*, write::*};
+use crate::util::text::{decode_text, TextEncoding};
+
+/**
+ * Reads the raw fields of a LIST INFO chunk. `size` is the size of the list without the list type.
+ * The text is not decoded yet, as the character set might be declared by a CSET chunk after the list.
+ */
+pub fn read_info_list<R: SizedDataRead>(source: &mut R, size: u32) -> Result<Vec<(FourCC, Vec<u8>)>, WaveDecodeError> {
+    let mut fields: Vec<(FourCC, Vec<u8>)> = Vec::new();
+    let mut count: u32 = 0;
+    while count + 8 <= size {
+        let id = FourCC::from_u32(source.read_u32(Endian::Little)?);
+        let info_size = source.read_u32(Endian::Little)?;
+        count += 8;
+        if info_size > size - count {
+            return Err(WaveDecodeError::invalid_chunk(CHUNK_LIST, format!("INFO field {} exceeds LIST chunk", id)));
+        }
+
+        fields.push((id, source.read_bytes(info_size)?));
+        count += info_size;
+
+        // Text information needs to be word-aligned (2-byte aligned)
+        if info_size % 2 == 1 && count < size {
+            source.read_u8()?;
+            count += 1;
+        }
+    }
+
+    Ok(fields)
+}
+
+/**
+ * Decodes the raw INFO fields into `metadata`. Every field is added to `metadata.info`, known
+ * fields are additionally stored in their typed field. Text is decoded with `charset` if the file
+ * declares one, otherwise as UTF-8 with `fallback` for invalid UTF-8.
+ */
+pub fn apply_info_list(metadata: &mut AudioMetadata, fields: &[(FourCC, Vec<u8>)], charset: Option<TextEncoding>, fallback: TextEncoding) {
+    for (id, bytes) in fields {
+        let value = match charset {
+            Some(x) => x.decode(bytes),
+            None => decode_text(bytes, fallback),
+        };
+
+        if let Some(field) = metadata.field_mut(*id) {
+            *field = Some(value.clone());
+        }
+        metadata.info.push((*id, value));
+    }
+}
+
+/**
+ * Writes a complete LIST INFO chunk with the fields returned by `AudioMetadata::info_entries`,
+ * text is encoded with `encoding`. Nothing is written if there are no fields.
+ */
+pub fn write_info_list<W: SizedDataWrite>(sink: &mut W, metadata: &AudioMetadata, encoding: TextEncoding) -> Result<(), IoError> {
+    let entries: Vec<(FourCC, Vec<u8>)> = metadata.info_entries().into_iter()
+        .map(|(id, value)| (id, encoding.encode(&value).into_owned()))
+        .collect();
+    if entries.is_empty() {
+        return Ok(());
+    }
+
+    // Every value is NUL-terminated and padded to an even length.
+    let size: u32 = entries.iter()
+        .map(|(_, value)| 8 + info_value_size(value) + info_value_size(value) % 2)
+        .sum::<u32>() + 4;
+
+    sink.write_u32(CHUNK_LIST, Endian::Little)?;
+    sink.write_u32(size, Endian::Little)?;
+    sink.write_u32(CHUNK_LIST_SUB_INFO, Endian::Little)?;
+    for (id, value) in &entries {
+        let value_size = info_value_size(value);
+        sink.write_u32(id.as_u32(), Endian::Little)?;
+        sink.write_u32(value_size, Endian::Little)?;
+        sink.write_all(value)?;
+        sink.write_u8(0)?;
+        if value_size % 2 == 1 {
+            sink.write_u8(0)?;
+        }
+    }
+
+    Ok(())
+}
+
+#[inline(always)]
+fn info_value_size(value: &[u8]) -> u32 {
+    (value.len() as u32) + 1
+}
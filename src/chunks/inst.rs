@@ -0,0 +1,80 @@
+use std::io::Error as IoError;
+
+use crate::WaveDecodeError;
+use crate::read::CHUNK_INST;
+use crate::util::exts::{Endian, read::*, write::*};
+
+/* The instrument chunk always has 7 bytes of payload, followed by a pad byte. */
+const INST_SIZE: u32 = 7;
+
+/**
+ * Contents of the `inst` chunk, which is used by samplers and DAWs to describe how the
+ * sample should be mapped onto a keyboard.
+ */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct InstrumentChunk {
+    pub unshifted_note: u8,         /* MIDI note number at which the sample is played back without pitch shift (0 - 127) */
+    pub fine_tune: i8,              /* Pitch shift adjustment in cents (-50 - 50) */
+    pub gain: i8,                   /* Gain in dB that should be applied to the sample (-64 - 64) */
+    pub low_note: u8,               /* Lowest MIDI note of the key range */
+    pub high_note: u8,              /* Highest MIDI note of the key range */
+    pub low_velocity: u8,           /* Lowest MIDI velocity of the velocity range */
+    pub high_velocity: u8,          /* Highest MIDI velocity of the velocity range */
+}
+
+impl InstrumentChunk {
+
+    pub fn new() -> InstrumentChunk {
+        InstrumentChunk {
+            unshifted_note: 60,
+            fine_tune: 0,
+            gain: 0,
+            low_note: 0,
+            high_note: 127,
+            low_velocity: 1,
+            high_velocity: 127,
+        }
+    }
+
+    /**
+     * Reads the chunk payload, `size` is the size stated in the chunk header.
+     */
+    pub fn read<R: SizedDataRead>(source: &mut R, size: u32) -> Result<InstrumentChunk, WaveDecodeError> {
+        if size < INST_SIZE {
+            return Err(WaveDecodeError::invalid_chunk(CHUNK_INST, format!("Unexpected inst chunk length: {}", size)));
+        }
+
+        Ok(InstrumentChunk {
+            unshifted_note: source.read_u8()?,
+            fine_tune: source.read_i8()?,
+            gain: source.read_i8()?,
+            low_note: source.read_u8()?,
+            high_note: source.read_u8()?,
+            low_velocity: source.read_u8()?,
+            high_velocity: source.read_u8()?,
+        })
+    }
+
+    /**
+     * Writes the complete chunk including chunk header and pad byte.
+     */
+    pub fn write<W: SizedDataWrite>(&self, sink: &mut W) -> Result<(), IoError> {
+        sink.write_u32(CHUNK_INST, Endian::Little)?;
+        sink.write_u32(INST_SIZE, Endian::Little)?;
+        sink.write_u8(self.unshifted_note)?;
+        sink.write_i8(self.fine_tune)?;
+        sink.write_i8(self.gain)?;
+        sink.write_u8(self.low_note)?;
+        sink.write_u8(self.high_note)?;
+        sink.write_u8(self.low_velocity)?;
+        sink.write_u8(self.high_velocity)?;
+        sink.write_u8(0)
+    }
+}
+
+impl Default for InstrumentChunk {
+
+    fn default() -> InstrumentChunk {
+        InstrumentChunk::new()
+    }
+}
@@ -0,0 +1,188 @@
+use std::time::Duration;
+
+/**
+ * Commonly used fields of an iXML document as written by field recorders (Sound Devices,
+ * Zoom, Aaton, ...). The complete document is available as string through `WaveFile::ixml`,
+ * this struct only provides typed access to the fields needed for conforming.
+ */
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct IXml {
+    pub project: Option<String>,
+    pub scene: Option<String>,
+    pub take: Option<String>,
+    pub tape: Option<String>,
+    pub circled: Option<bool>,
+    pub file_uid: Option<String>,
+    pub note: Option<String>,
+    pub tracks: Vec<IXmlTrack>,
+    pub speed: Option<IXmlSpeed>,
+}
+
+/**
+ * A single `TRACK` entry of the `TRACK_LIST`.
+ */
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct IXmlTrack {
+    pub channel_index: Option<u16>,
+    pub interleave_index: Option<u16>,
+    pub name: Option<String>,
+    pub function: Option<String>,
+}
+
+/**
+ * Contents of the `SPEED` element which holds frame rate and timecode information.
+ */
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct IXmlSpeed {
+    pub note: Option<String>,
+    pub master_speed: Option<String>,
+    pub current_speed: Option<String>,
+    pub timecode_rate: Option<String>,
+    pub timecode_flag: Option<String>,
+    pub file_sample_rate: Option<u32>,
+    pub audio_bit_depth: Option<u16>,
+    pub digitizer_sample_rate: Option<u32>,
+    pub timestamp_samples_since_midnight: Option<u64>,
+    pub timestamp_sample_rate: Option<u32>,
+}
+
+impl IXmlSpeed {
+
+    /**
+     * Returns the time of day of the first sample, computed from the timestamp fields.
+     */
+    pub fn timestamp(&self) -> Option<Duration> {
+        match (self.timestamp_samples_since_midnight, self.timestamp_sample_rate) {
+            (Some(samples), Some(rate)) if rate != 0 => {
+                let secs = samples / (rate as u64);
+                let nanos = ((samples % (rate as u64)) * 1_000_000_000) / (rate as u64);
+                Some(Duration::new(secs, nanos as u32))
+            },
+            _ => None,
+        }
+    }
+}
+
+impl IXml {
+
+    /**
+     * Extracts the common fields from an iXML document. Missing or malformed fields are
+     * left empty, this function never fails.
+     */
+    pub fn parse(xml: &str) -> IXml {
+        let mut ixml = IXml {
+            project: text(xml, "PROJECT"),
+            scene: text(xml, "SCENE"),
+            take: text(xml, "TAKE"),
+            tape: text(xml, "TAPE"),
+            circled: text(xml, "CIRCLED").map(|x| x.eq_ignore_ascii_case("TRUE")),
+            file_uid: text(xml, "FILE_UID"),
+            note: None,
+            tracks: Vec::new(),
+            speed: None,
+        };
+
+        // NOTE is used both on top level and inside of SPEED, therefore look at the document without SPEED.
+        ixml.note = match element(xml, "SPEED") {
+            Some(speed) => {
+                let without_speed = xml.replacen(speed, "", 1);
+                text(&without_speed, "NOTE")
+            },
+            None => text(xml, "NOTE"),
+        };
+
+        if let Some(track_list) = element(xml, "TRACK_LIST") {
+            ixml.tracks = elements(track_list, "TRACK").into_iter()
+                .map(|track| IXmlTrack {
+                    channel_index: text(track, "CHANNEL_INDEX").and_then(|x| x.parse().ok()),
+                    interleave_index: text(track, "INTERLEAVE_INDEX").and_then(|x| x.parse().ok()),
+                    name: text(track, "NAME"),
+                    function: text(track, "FUNCTION"),
+                })
+                .collect();
+        }
+
+        if let Some(speed) = element(xml, "SPEED") {
+            let hi: Option<u64> = text(speed, "TIMESTAMP_SAMPLES_SINCE_MIDNIGHT_HI").and_then(|x| x.parse().ok());
+            let lo: Option<u64> = text(speed, "TIMESTAMP_SAMPLES_SINCE_MIDNIGHT_LO").and_then(|x| x.parse().ok());
+
+            ixml.speed = Some(IXmlSpeed {
+                note: text(speed, "NOTE"),
+                master_speed: text(speed, "MASTER_SPEED"),
+                current_speed: text(speed, "CURRENT_SPEED"),
+                timecode_rate: text(speed, "TIMECODE_RATE"),
+                timecode_flag: text(speed, "TIMECODE_FLAG"),
+                file_sample_rate: text(speed, "FILE_SAMPLE_RATE").and_then(|x| x.parse().ok()),
+                audio_bit_depth: text(speed, "AUDIO_BIT_DEPTH").and_then(|x| x.parse().ok()),
+                digitizer_sample_rate: text(speed, "DIGITIZER_SAMPLE_RATE").and_then(|x| x.parse().ok()),
+                timestamp_samples_since_midnight: match (hi, lo) {
+                    (Some(hi), Some(lo)) => Some((hi << 32) | lo),
+                    _ => None,
+                },
+                timestamp_sample_rate: text(speed, "TIMESTAMP_SAMPLE_RATE").and_then(|x| x.parse().ok()),
+            });
+        }
+
+        ixml
+    }
+}
+
+/**
+ * Returns the position right behind the opening tag `<tag ...>` and the position of
+ * the matching closing tag, searching from `from`.
+ */
+fn find_element(xml: &str, tag: &str, from: usize) -> Option<(usize, usize, usize)> {
+    let open = format!("<{}", tag);
+    let close = format!("</{}>", tag);
+
+    let mut pos = from;
+    loop {
+        let start = pos + xml[pos..].find(&open)?;
+        let after_name = start + open.len();
+        let tag_end = after_name + xml[after_name..].find('>')?;
+
+        match xml[after_name..].chars().next() {
+            // Make sure only the exact tag matches, e.g. TAKE must not match TAKE_TYPE
+            Some('>') | Some(' ') | Some('\t') | Some('\r') | Some('\n') | Some('/') => {
+                if xml[..tag_end].ends_with('/') {
+                    return Some((tag_end + 1, tag_end + 1, tag_end + 1));
+                }
+                let content_end = (tag_end + 1) + xml[(tag_end + 1)..].find(&close)?;
+                return Some((tag_end + 1, content_end, content_end + close.len()));
+            },
+            _ => pos = after_name,
+        }
+    }
+}
+
+fn element<'a>(xml: &'a str, tag: &str) -> Option<&'a str> {
+    find_element(xml, tag, 0).map(|(start, end, _)| &xml[start..end])
+}
+
+fn elements<'a>(xml: &'a str, tag: &str) -> Vec<&'a str> {
+    let mut found: Vec<&'a str> = Vec::new();
+    let mut pos = 0;
+    while let Some((start, end, next)) = find_element(xml, tag, pos) {
+        found.push(&xml[start..end]);
+        pos = next;
+    }
+    found
+}
+
+/**
+ * Returns the trimmed text content of the first element `tag`, with XML entities resolved.
+ * Empty elements are treated as not present.
+ */
+fn text(xml: &str, tag: &str) -> Option<String> {
+    let content = element(xml, tag)?.trim();
+    if content.is_empty() {
+        return None;
+    }
+
+    Some(content
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&"))
+}
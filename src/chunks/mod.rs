@@ -0,0 +1,132 @@
+pub mod acid;
+pub mod bext;
+pub mod cset;
+pub mod cue;
+pub mod handler;
+pub mod info;
+pub mod inst;
+pub mod ixml;
+
+use std::cmp::min;
+use std::fmt::{Debug, Formatter, Result as FmtResult};
+use std::ops::Range;
+use std::sync::Arc;
+
+use crate::FourCC;
+
+/**
+ * The buffer a file was decoded from, shared by all chunks whose payload lies in it.
+ */
+#[derive(Clone)]
+pub(crate) struct SharedSource(Arc<dyn AsRef<[u8]> + Send + Sync>);
+
+impl SharedSource {
+
+    pub(crate) fn new<B: AsRef<[u8]> + Send + Sync + 'static>(bytes: Arc<B>) -> SharedSource {
+        SharedSource(bytes)
+    }
+
+    fn bytes(&self) -> &[u8] {
+        self.0.as_ref().as_ref()
+    }
+}
+
+impl Debug for SharedSource {
+
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "SharedSource({} B)", self.bytes().len())
+    }
+}
+
+/**
+ * Payload of a chunk. Chunks of a decoded file only refer to the source, their bytes are
+ * not copied unless the chunk is modified or the source is released.
+ */
+#[derive(Clone, Debug)]
+pub(crate) enum Payload {
+    Owned(Vec<u8>),
+    Pending(Range<usize>),                          /* Range of the source that is still being decoded */
+    Shared(SharedSource, Range<usize>),
+}
+
+impl Payload {
+
+    pub(crate) fn bytes(&self) -> &[u8] {
+        match self {
+            Payload::Owned(x) => x,
+            Payload::Pending(_) => &[],
+            Payload::Shared(source, range) => source.bytes().get(range.clone()).unwrap_or(&[]),
+        }
+    }
+}
+
+/**
+ * A chunk as it was found in the file. The raw payload is available for every chunk except
+ * `data`, whose payload is the sample data of the `WaveFile` (see `WaveFile::chunk_data`).
+ */
+#[derive(Clone, Debug)]
+pub struct Chunk {
+    pub id: FourCC,
+    pub offset: u64,                /* Offset of the chunk header from the beginning of the file */
+    pub size: u32,                  /* Payload size as stated in the chunk header, without pad byte */
+
+    pub(crate) data: Payload,
+}
+
+impl Chunk {
+
+    /**
+     * Creates a new chunk that should be written in addition to the chunks of a file.
+     */
+    pub fn new(id: FourCC, data: Vec<u8>) -> Chunk {
+        Chunk { id, offset: 0, size: data.len() as u32, data: Payload::Owned(data) }
+    }
+
+    /**
+     * Creates the chunk entry for the chunk whose header starts at `offset` in `source`.
+     * The payload is truncated if the chunk exceeds the source, it is attached to the
+     * source with `share` once decoding is finished.
+     */
+    pub(crate) fn from_source(source: &[u8], id: u32, offset: u64, keep_data: bool) -> Chunk {
+        let start = min(offset as usize + 8, source.len());
+        let size = match source.get((offset as usize + 4)..start) {
+            Some(x) if x.len() == 4 => u32::from_le_bytes([ x[0], x[1], x[2], x[3] ]),
+            _ => 0,
+        };
+        let end = min(start + size as usize, source.len());
+
+        Chunk {
+            id: FourCC::from_u32(id),
+            offset,
+            size,
+            data: match keep_data {
+                true => Payload::Pending(start..end),
+                false => Payload::Owned(Vec::new()),
+            },
+        }
+    }
+
+    /**
+     * Refers the payload to `source`. Bytes of the source at and behind `removed` were
+     * moved to the front by its length, as the sample data is not kept in the source.
+     */
+    pub(crate) fn share(&mut self, source: &SharedSource, removed: &Range<usize>) {
+        if let Payload::Pending(range) = &self.data {
+            let shift = match range.start >= removed.end {
+                true => removed.len(),
+                false => 0,
+            };
+            self.data = Payload::Shared(source.clone(), (range.start - shift)..(range.end - shift));
+        }
+    }
+
+    /**
+     * Copies the payload, so the chunk no longer refers to the source.
+     */
+    #[cfg(feature = "mmap")]
+    pub(crate) fn detach(&mut self) {
+        if let Payload::Shared(..) = self.data {
+            self.data = Payload::Owned(self.data.bytes().to_vec());
+        }
+    }
+}
@@ -0,0 +1,128 @@
+use crate::util::math::int_limits;
+
+/* E-weighted noise shaping filter of Lipshitz et al., "Minimally Audible Noise Shaping" (1991) */
+const LIPSHITZ_COEFFS: [f64; 5] = [ 2.033, -2.165, 1.959, -1.590, 0.6149 ];
+
+/**
+ * Probability distribution of the noise added before quantization. Amplitudes are given in
+ * units of the least significant bit of the result.
+ */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DitherKind {
+    None,                   /* Plain rounding */
+    Rectangular,            /* Uniform noise of 1 LSB peak-to-peak */
+    Triangular,             /* Sum of two uniform values (TPDF), 2 LSB peak-to-peak */
+    HighPassTriangular,     /* Difference of successive uniform values, TPDF with the noise moved to high frequencies */
+}
+
+/**
+ * Filter that feeds the quantization error back, moving the noise to less audible frequencies.
+ */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum NoiseShaping {
+    None,
+    FirstOrder,             /* Error of the previous sample, +6 dB per octave */
+    SecondOrder,
+    Lipshitz,               /* 5-tap filter following the sensitivity of the ear */
+}
+
+impl NoiseShaping {
+
+    fn coeffs(&self) -> &'static [f64] {
+        match *self {
+            NoiseShaping::None => &[],
+            NoiseShaping::FirstOrder => &[ 1.0 ],
+            NoiseShaping::SecondOrder => &[ 2.0, -1.0 ],
+            NoiseShaping::Lipshitz => &LIPSHITZ_COEFFS,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+struct ChannelState {
+    previous: f64,                          /* Last uniform value, for HighPassTriangular */
+    errors: [f64; LIPSHITZ_COEFFS.len()],   /* Quantization errors, most recent first */
+}
+
+/**
+ * Reduces the bit depth of samples with dither and noise shaping. The noise is generated from
+ * a seed, so the same seed always gives the same result.
+ */
+#[derive(Clone, Debug)]
+pub struct Ditherer {
+    kind: DitherKind,
+    shaping: NoiseShaping,
+    state: u64,
+    channels: Vec<ChannelState>,
+}
+
+impl Ditherer {
+
+    pub fn new(kind: DitherKind, shaping: NoiseShaping, seed: u64) -> Ditherer {
+        Ditherer { kind, shaping, state: seed, channels: Vec::new() }
+    }
+
+    pub fn kind(&self) -> DitherKind {
+        self.kind
+    }
+
+    pub fn shaping(&self) -> NoiseShaping {
+        self.shaping
+    }
+
+    /**
+     * Quantizes `val`, given in units of the least significant bit of the result, to an integer
+     * sample of `bits` bits. Channels are filtered independently, so interleaved samples have
+     * to be passed with their channel.
+     */
+    pub fn quantize(&mut self, val: f64, channel: usize, bits: u16) -> i64 {
+        while channel >= self.channels.len() {
+            // High-pass TPDF subtracts the previous value, without one the first sample only gets rectangular dither.
+            let previous = match self.kind {
+                DitherKind::HighPassTriangular => self.uniform(),
+                _ => 0.0,
+            };
+            self.channels.push(ChannelState { previous, ..ChannelState::default() });
+        }
+
+        let coeffs = self.shaping.coeffs();
+        let errors = self.channels[channel].errors;
+        let shaped = val - coeffs.iter().zip(errors.iter()).map(|(c, e)| c * e).sum::<f64>();
+        let noise = self.noise(channel);
+
+        let (min, max) = int_limits(bits);
+        // Rounds half up like `Rounding::Nearest`
+        let quantized = ((shaped + noise + 0.5).floor() as i64).clamp(min, max);
+
+        // The error is limited, so clipping cannot make the feedback loop unstable.
+        let state = &mut self.channels[channel];
+        state.errors.rotate_right(1);
+        state.errors[0] = ((quantized as f64) - shaped).clamp(-2.0, 2.0);
+
+        quantized
+    }
+
+    fn noise(&mut self, channel: usize) -> f64 {
+        match self.kind {
+            DitherKind::None => 0.0,
+            DitherKind::Rectangular => self.uniform(),
+            DitherKind::Triangular => self.uniform() + self.uniform(),
+            DitherKind::HighPassTriangular => {
+                let val = self.uniform();
+                let previous = std::mem::replace(&mut self.channels[channel].previous, val);
+                val - previous
+            },
+        }
+    }
+
+    /* Uniform value in [-0.5, 0.5), generated with SplitMix64 */
+    fn uniform(&mut self) -> f64 {
+        self.state = self.state.wrapping_add(0x9e3779b97f4a7c15);
+        let mut x = self.state;
+        x = (x ^ (x >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        x = (x ^ (x >> 27)).wrapping_mul(0x94d049bb133111eb);
+        x ^= x >> 31;
+
+        ((x >> 11) as f64) / ((1_u64 << 53) as f64) - 0.5
+    }
+}
@@ -0,0 +1,253 @@
+use crate::{FourCC, WaveDecodeError, WaveFile, WaveFormat};
+use crate::read::*;
+use crate::util::exts::{Endian, read::*, write::*};
+use crate::write::{encode_chunks, EncodedChunk};
+
+use std::fs::File;
+use std::io::{Cursor, Error as IoError, Read, Seek, SeekFrom, Write};
+
+/* Chunks that only reserve space and are dropped when the metadata is laid out again */
+const FILLER_CHUNKS: [&[u8; 4]; 4] = [ b"JUNK", b"junk", b"PAD ", b"FLLR" ];
+
+/**
+ * Sources that can be truncated, required to remove metadata that was placed behind the sample data.
+ */
+pub trait SetLen {
+
+    fn set_len(&mut self, len: u64) -> Result<(), IoError>;
+}
+
+impl SetLen for File {
+
+    fn set_len(&mut self, len: u64) -> Result<(), IoError> {
+        File::set_len(self, len)
+    }
+}
+
+impl SetLen for Cursor<Vec<u8>> {
+
+    fn set_len(&mut self, len: u64) -> Result<(), IoError> {
+        self.get_mut().resize(len as usize, 0);
+        Ok(())
+    }
+}
+
+/**
+ * Edits the metadata of an existing WAVE file in place. Only the chunks in front of and
+ * behind the sample data are rewritten, the data chunk itself is never touched or moved.
+ *
+ * If the new metadata fits into the space in front of the data chunk, it is written there
+ * and the remaining space is filled with a `JUNK` chunk. Otherwise everything except the
+ * format and fact chunk is relocated behind the data chunk.
+ */
+pub struct WaveEditor<F>
+where F: Read + Write + Seek + SetLen {
+    file: F,
+    wave_file: WaveFile,
+    data_offset: u64,               /* Offset of the data chunk header */
+    data_size: u32,
+}
+
+impl<F> WaveEditor<F>
+where F: Read + Write + Seek + SetLen {
+
+    /**
+     * Reads all chunks except the sample data.
+     */
+    pub fn open(mut file: F) -> Result<WaveEditor<F>, WaveDecodeError> {
+        let (wave_file, data_offset, data_size) = scan(&mut file)?;
+        Ok(WaveEditor { file, wave_file, data_offset, data_size })
+    }
+
+    /**
+     * Returns the decoded file. It does not contain any sample data, sample related
+     * information like the format is available nevertheless.
+     */
+    pub fn wave_file(&self) -> &WaveFile {
+        &self.wave_file
+    }
+
+    /**
+     * Returns the decoded file for editing metadata, `bext`, cue points etc. Changes to the
+     * format are not supported, as the sample data is not rewritten.
+     */
+    pub fn wave_file_mut(&mut self) -> &mut WaveFile {
+        &mut self.wave_file
+    }
+
+    pub fn into_inner(self) -> F {
+        self.file
+    }
+
+    /**
+     * Writes the edited metadata to the file.
+     */
+    pub fn save(&mut self) -> Result<(), WaveDecodeError> {
+        for filler in FILLER_CHUNKS {
+            self.wave_file.chunks.retain(|x| &x.id.0 != filler);
+        }
+        if self.wave_file.format != WaveFormat::Pcm && self.wave_file.num_of_samples.is_none() && self.wave_file.frame_size != 0 {
+            self.wave_file.num_of_samples = Some( self.data_size / (self.wave_file.frame_size as u32) );
+        }
+
+        let mut chunks = encode_chunks(&self.wave_file)?;
+        let data_pos = chunks.iter().position(|x| matches!(x, EncodedChunk::Data)).unwrap_or(chunks.len());
+        let after: Vec<EncodedChunk> = chunks.split_off(data_pos).into_iter()
+            .filter(|x| !matches!(x, EncodedChunk::Data))
+            .collect();
+        let mut head = chunks;
+        let mut moved: Vec<EncodedChunk> = Vec::new();
+
+        let available = self.data_offset - 12;
+        if !fits(encoded_size(&head), available) {
+            // Only the chunks that are needed to interpret the sample data stay in front of it.
+            let (stay, go): (Vec<EncodedChunk>, Vec<EncodedChunk>) = head.into_iter()
+                .partition(|x| match x {
+                    EncodedChunk::Bytes(bytes) => bytes.starts_with(b"fmt ") || bytes.starts_with(b"fact"),
+                    EncodedChunk::Data => false,
+                });
+            if !fits(encoded_size(&stay), available) {
+                return Err(WaveDecodeError::Unsupported(String::from("Format chunk does not fit in front of the sample data")));
+            }
+            head = stay;
+            moved = go;
+        }
+
+        // The size is checked up front, so the file is left untouched if the edit cannot be saved.
+        let data_end = self.data_offset + 8 + (self.data_size as u64) + (self.data_size % 2) as u64;
+        let file_end = data_end + encoded_size(&moved) + encoded_size(&after);
+        if file_end - 8 > (u32::MAX as u64) {
+            return Err(WaveDecodeError::Unsupported(String::from("WAVE file exceeds 4 GiB")));
+        }
+
+        self.file.seek(SeekFrom::Start(12))?;
+        write_chunks(&mut self.file, &head)?;
+        let remaining = available - encoded_size(&head);
+        if remaining > 0 {
+            self.file.write_u32(FourCC(*b"JUNK").as_u32(), Endian::Little)?;
+            self.file.write_u32((remaining - 8) as u32, Endian::Little)?;
+            self.file.write_all(&vec![0; (remaining - 8) as usize])?;
+        }
+
+        self.file.seek(SeekFrom::Start(data_end))?;
+        write_chunks(&mut self.file, &moved)?;
+        write_chunks(&mut self.file, &after)?;
+        self.file.set_len(file_end)?;
+
+        self.file.seek(SeekFrom::Start(4))?;
+        self.file.write_u32((file_end - 8) as u32, Endian::Little)?;
+        self.file.flush()?;
+
+        // Read the chunk table again, so offsets reflect the new layout.
+        let (wave_file, data_offset, data_size) = scan(&mut self.file)?;
+        self.wave_file = wave_file;
+        self.data_offset = data_offset;
+        self.data_size = data_size;
+
+        Ok(())
+    }
+}
+
+/**
+ * A region of `available` bytes can be filled exactly or with a trailing JUNK chunk, which needs at least 8 bytes.
+ */
+#[inline(always)]
+fn fits(size: u64, available: u64) -> bool {
+    size == available || size + 8 <= available
+}
+
+fn encoded_size(chunks: &[EncodedChunk]) -> u64 {
+    chunks.iter()
+        .map(|x| match x {
+            EncodedChunk::Bytes(bytes) => bytes.len() as u64,
+            EncodedChunk::Data => 0,
+        })
+        .sum()
+}
+
+fn write_chunks<W: Write>(sink: &mut W, chunks: &[EncodedChunk]) -> Result<(), IoError> {
+    for chunk in chunks {
+        if let EncodedChunk::Bytes(bytes) = chunk {
+            sink.write_all(bytes)?;
+        }
+    }
+    Ok(())
+}
+
+/**
+ * Decodes all chunks of `file` except the payload of the data chunk. Returns the decoded file
+ * together with offset and size of the data chunk.
+ */
+fn scan<F: Read + Seek>(file: &mut F) -> Result<(WaveFile, u64, u32), WaveDecodeError> {
+    let file_len = file.seek(SeekFrom::End(0))?;
+    file.seek(SeekFrom::Start(0))?;
+    let file_header = file.read_u32(Endian::Little)?;
+    let riff_size = file.read_u32(Endian::Little)?;        /* Only checked for streamed files, it is recomputed when saving */
+    let file_format = file.read_u32(Endian::Little)?;
+    if file_header != RIFF_MAGIC || file_format != WAVE_MAGIC {
+        return Err(WaveDecodeError::InvalidHeader);
+    }
+
+    // The metadata is collected into a WAVE file with an empty data chunk, which is decoded as usual.
+    let mut buf: Vec<u8> = Vec::new();
+    buf.write_u32(RIFF_MAGIC, Endian::Little)?;
+    buf.write_u32(0, Endian::Little)?;
+    buf.write_u32(WAVE_MAGIC, Endian::Little)?;
+
+    let mut offsets: Vec<u64> = Vec::new();
+    let mut data: Option<(u64, u32)> = None;
+    let mut pos: u64 = 12;
+    while pos + 8 <= file_len {
+        file.seek(SeekFrom::Start(pos))?;
+        let id = file.read_u32(Endian::Little)?;
+        let size = file.read_u32(Endian::Little)?;
+        offsets.push(pos);
+
+        buf.write_u32(id, Endian::Little)?;
+        match id {
+            CHUNK_DATA => {
+                // Sizes of unfinished recordings cannot be trusted. Saving would append zeros as sample data
+                // or the samples of a streamed file would be read as chunks.
+                let streamed = riff_size == 0 || riff_size == u32::MAX;
+                if pos + 8 + (size as u64) > file_len || (size == 0 && streamed && pos + 8 < file_len) {
+                    return Err(WaveDecodeError::Unsupported(String::from("Data chunk size of an unfinished recording, use repair::repair first")));
+                }
+                data = Some((pos, size));
+                buf.write_u32(0, Endian::Little)?;
+            },
+            _ => {
+                buf.write_u32(size, Endian::Little)?;
+                buf.extend(file.read_bytes(size)?);
+                if size % 2 == 1 {
+                    buf.write_u8(0)?;
+                }
+            },
+        };
+        pos += 8 + (size as u64) + (size % 2) as u64;
+    }
+
+    let riff_size = (buf.len() - 8) as u32;
+    buf[4..8].copy_from_slice(&riff_size.to_le_bytes());
+
+    let (data_offset, data_size) = match data {
+        Some(x) => x,
+        None => return Err(WaveDecodeError::MissingChunk(FourCC::from_u32(CHUNK_DATA))),
+    };
+
+    // Lists other than INFO, e.g. the labels of cue points, are kept as raw chunks in lenient mode.
+    let mut wave_file = WaveReader::with_options(Cursor::new(buf), DecodeOptions::lenient()).decode()?;
+    for warning in wave_file.warnings.iter_mut() {
+        let index = wave_file.chunks.iter().position(|x| Some(x.offset) == warning.offset());
+        if let (Some(i), Some(offset)) = (index, warning.offset_mut()) {
+            *offset = offsets[i];
+        }
+    }
+    for (chunk, offset) in wave_file.chunks.iter_mut().zip(offsets) {
+        chunk.offset = offset;
+        if chunk.id.as_u32() == CHUNK_DATA {
+            chunk.size = data_size;
+        }
+    }
+
+    Ok((wave_file, data_offset, data_size))
+}
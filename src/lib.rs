@@ -1,17 +1,35 @@
 pub mod util;
+pub mod chunks;
 pub mod read;
+pub mod edit;
+pub mod write;
+pub mod validate;
+pub mod repair;
+pub mod raw;
+pub mod au;
+pub mod caf;
+pub mod probe;
+pub mod dither;
+#[cfg(feature = "mmap")]
+pub mod mmap;
+#[allow(dead_code, unused_imports)]
 pub mod test;
 
 use std::string::String;
-use std::io::{Error as IoError, ErrorKind, Cursor};
+use std::borrow::Cow;
+use std::io::{Error as IoError, ErrorKind, Cursor, Read, Write};
 use std::time::Duration;
+use std::any::Any;
+use std::sync::Arc;
 
 use std::convert::From;
 use std::fmt::{Display, Debug, Formatter, Result as FmtResult};
 use std::error::Error;
 
-use util::exts::{Endian, read::*};
-use util::math;
+use util::exts::{Endian, write::*};
+use util::{g711, math, math::{Rounding, Scaling}};
+use dither::{Ditherer, DitherKind, NoiseShaping};
+use chunks::{Chunk, acid::AcidChunk, bext::BroadcastExtension, cue::CuePoint, cset::CharacterSet, inst::InstrumentChunk, ixml::IXml};
 
 #[allow(non_camel_case_types)]
 #[derive(PartialEq, Clone, Copy, Debug)]
@@ -57,153 +75,754 @@ impl WaveFormat {
 }
 
 
-pub trait Sample: Sized + std::fmt::Debug { 
+pub trait Sample: Sized + Copy + Default + std::fmt::Debug { 
 
-    fn read(cursor: &mut Cursor<Vec<u8>>, format: WaveFormat, bits: u16) -> Result<Self, IoError>;
+    /* Width of the type, for narrowing conversions */
+    const BITS: u16;
+
+    /**
+     * Decodes as many whole samples of `data` as fit into `out` and returns their number.
+     * Trailing bytes that do not form a whole sample are ignored.
+     */
+    fn read_slice(data: &[u8], format: WaveFormat, bits: u16, out: &mut [Self]) -> Result<usize, WaveDecodeError>;
+
+    fn read(cursor: &mut Cursor<Vec<u8>>, format: WaveFormat, bits: u16) -> Result<Self, WaveDecodeError> {
+        // An empty slice only validates format and bit depth, so nothing is consumed on error.
+        Self::read_slice(&[], format, bits, &mut [])?;
+
+        let mut buf: [u8; 8] = [0; 8];
+        let bytes = &mut buf[..(bits as usize / 8).clamp(1, 8)];
+        cursor.read_exact(bytes)?;
+
+        let mut sample = [Self::default()];
+        Self::read_slice(bytes, format, bits, &mut sample)?;
+        Ok(sample[0])
+    }
+
+    /**
+     * Borrows `data` as samples of this type without conversion. Only possible if the samples are
+     * stored in exactly this type, the host is little-endian and `data` is suitably aligned.
+     */
+    fn view(_data: &[u8], _format: WaveFormat, _bits: u16) -> Option<&[Self]> {
+        None
+    }
+
+    /**
+     * Encodes the sample with the given format and bit depth. Integer PCM is rounded to the
+     * nearest value and clipped, IEEE float is written without clipping.
+     */
+    fn write<W: Write>(&self, sink: &mut W, format: WaveFormat, bits: u16) -> Result<(), WaveDecodeError>;
+
+    /* Conversions from and to integer PCM of `bits` bits and normalized floats, see `Scaling` */
+    fn from_int(val: i64, bits: u16, scaling: Scaling) -> Self;
+    fn from_float(val: f64, scaling: Scaling) -> Self;
+    fn to_int(&self, bits: u16, scaling: Scaling) -> i64;
+    fn to_float(&self, scaling: Scaling) -> f64;
+
+    /**
+     * Like `read_slice`, with the given scaling policy.
+     */
+    fn read_slice_scaled(data: &[u8], format: WaveFormat, bits: u16, scaling: Scaling, out: &mut [Self]) -> Result<usize, WaveDecodeError> {
+        // Format and bit depth are supported for the same types as by `read_slice`.
+        Self::read_slice(&[], format, bits, &mut [])?;
+
+        Ok(match (format, bits) {
+            (WaveFormat::Pcm, 8) => convert(data, out, |[x]| Self::from_int((x as i64) - 128, 8, scaling)),
+            (WaveFormat::Pcm, 16) => convert(data, out, |x| Self::from_int(i16::from_le_bytes(x) as i64, 16, scaling)),
+            (WaveFormat::Pcm, 24) => convert(data, out, |x| Self::from_int(i24_from_le_bytes(x) as i64, 24, scaling)),
+            (WaveFormat::Pcm, 32) => convert(data, out, |x| Self::from_int(i32::from_le_bytes(x) as i64, 32, scaling)),
+            (WaveFormat::Pcm, 64) => convert(data, out, |x| Self::from_int(i64::from_le_bytes(x), 64, scaling)),
+            (WaveFormat::IeeeFloat, 32) => convert(data, out, |x| Self::from_float(f32::from_le_bytes(x) as f64, scaling)),
+            (WaveFormat::IeeeFloat, 64) => convert(data, out, |x| Self::from_float(f64::from_le_bytes(x), scaling)),
+            (WaveFormat::Alaw, _) => convert(data, out, |[x]| Self::from_int(g711::expand_alaw(x) as i64, 16, scaling)),
+            (WaveFormat::Mulaw, _) => convert(data, out, |[x]| Self::from_int(g711::expand_mulaw(x) as i64, 16, scaling)),
+            _ => 0,
+        })
+    }
+
+    /**
+     * Like `write`, with the given scaling policy.
+     */
+    fn write_scaled<W: Write>(&self, sink: &mut W, format: WaveFormat, bits: u16, scaling: Scaling) -> Result<(), WaveDecodeError> {
+        match format {
+            WaveFormat::Pcm => match bits {
+                8 => sink.write_u8((self.to_int(8, scaling) + 128) as u8)?,
+                16 => sink.write_i16(self.to_int(16, scaling) as i16, Endian::Little)?,
+                24 => sink.write_i24(self.to_int(24, scaling) as i32, Endian::Little)?,
+                32 => sink.write_i32(self.to_int(32, scaling) as i32, Endian::Little)?,
+                64 => sink.write_i64(self.to_int(64, scaling), Endian::Little)?,
+                _ => return Err(WaveDecodeError::UnsupportedBitDepth { format, bits }),
+            },
+            WaveFormat::IeeeFloat => match bits {
+                32 => sink.write_f32(self.to_float(scaling) as f32, Endian::Little)?,
+                64 => sink.write_f64(self.to_float(scaling), Endian::Little)?,
+                _ => return Err(WaveDecodeError::UnsupportedBitDepth { format, bits }),
+            },
+            _ => return Err(WaveDecodeError::UnsupportedFormat(format)),
+        };
+        Ok(())
+    }
+}
+
+/**
+ * How samples are decoded into an integer type that is narrower than the samples of the file,
+ * e.g. 24-bit PCM or float into `i16`. Samples are scaled by the scaling policy first, values
+ * that still exceed the range of the type are clipped.
+ */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Narrowing {
+    Refuse,                         /* Fails with `IncompatibleSampleType` */
+    Truncate,                       /* Drops the least significant bits */
+    Round,                          /* Rounds to the nearest value */
+    Dither(DitherKind, u64),        /* Rounds after adding dither of the given kind, generated from the seed */
+}
+
+/**
+ * Options that control how samples are converted into the requested type. The scaling policy
+ * applies to narrowing as well, with `Scaling::Shift` integer PCM is narrowed bit-accurately.
+ */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SampleOptions {
+    pub scaling: Scaling,
+    pub narrowing: Narrowing,
+}
+
+impl SampleOptions {
+
+    pub fn new() -> SampleOptions {
+        SampleOptions { scaling: Scaling::Asymmetric, narrowing: Narrowing::Refuse }
+    }
+}
+
+impl Default for SampleOptions {
+
+    fn default() -> SampleOptions {
+        SampleOptions::new()
+    }
+}
+
+/**
+ * Maps each whole block of `N` bytes of `data` onto `out`. The loop has no branches or error
+ * handling, so it can be vectorized.
+ */
+#[inline(always)]
+fn convert<S, const N: usize>(data: &[u8], out: &mut [S], map: impl Fn([u8; N]) -> S) -> usize {
+    let len = (data.len() / N).min(out.len());
+    for (bytes, sample) in data.chunks_exact(N).zip(out[..len].iter_mut()) {
+        *sample = map(bytes.try_into().unwrap());
+    }
+    len
+}
+
+/**
+ * Reinterprets `data` as slice of `S`, an incomplete sample at the end is left out.
+ *
+ * Safety: every bit pattern must be a valid `S`, i.e. `S` is a primitive integer or float.
+ */
+unsafe fn view_native<S: Sample>(data: &[u8]) -> Option<&[S]> {
+    if cfg!(target_endian = "big") {
+        return None;
+    }
+
+    let (head, samples, _) = data.align_to::<S>();
+    match head.is_empty() {
+        true => Some(samples),
+        false => None,
+    }
+}
+
+#[inline(always)]
+fn i24_from_le_bytes(bytes: [u8; 3]) -> i32 {
+    i32::from_le_bytes([ 0, bytes[0], bytes[1], bytes[2] ]) >> 8
+}
+
+/**
+ * Encodes a sample normalized to [-1.0, 1.0], this is the common path of `Sample::write`.
+ */
+fn write_normalized<W: Write>(sink: &mut W, val: f64, format: WaveFormat, bits: u16) -> Result<(), WaveDecodeError> {
+    match format {
+        WaveFormat::Pcm => match bits {
+            8 => sink.write_u8(math::map_f64_to_u8(val))?,
+            16 => sink.write_i16(math::map_f64_to_i16(val), Endian::Little)?,
+            24 => sink.write_i24(math::map_f64_to_i24(val), Endian::Little)?,
+            32 => sink.write_i32(math::map_f64_to_i32(val), Endian::Little)?,
+            64 => sink.write_i64(math::map_f64_to_i64(val), Endian::Little)?,
+            _ => return Err(WaveDecodeError::UnsupportedBitDepth { format, bits }),
+        },
+        WaveFormat::IeeeFloat => match bits {
+            32 => sink.write_f32(val as f32, Endian::Little)?,
+            64 => sink.write_f64(val, Endian::Little)?,
+            _ => return Err(WaveDecodeError::UnsupportedBitDepth { format, bits }),
+        },
+        _ => return Err(WaveDecodeError::UnsupportedFormat(format)),
+    };
+    Ok(())
 }
 
 impl Sample for u8 {
 
-    fn read(cursor: &mut Cursor<Vec<u8>>, format: WaveFormat, bits: u16) -> Result<Self, IoError> {
+    const BITS: u16 = 8;
+
+    fn read_slice(data: &[u8], format: WaveFormat, bits: u16, out: &mut [Self]) -> Result<usize, WaveDecodeError> {
         match format {
             WaveFormat::Pcm => {
                 match bits {
-                    8 => Ok( cursor.read_u8()? ),
+                    8 => Ok( convert(data, out, |[x]| x) ),
 
                     /* Unsupported cases */
-                    b if b > 8 => Err(IoError::new(ErrorKind::Unsupported, format!("{} bits too large for u8", bits))),
-                    b if (b % 8 == 0) => Err(IoError::new(ErrorKind::Unsupported, "Bits per sample must be multiple of 8")),
-                    _ => Err(IoError::new(ErrorKind::Unsupported, "Unsupported bits per sample"))
+                    b if b > 8 => Err(WaveDecodeError::IncompatibleSampleType { format, bits, sample_type: std::any::type_name::<Self>() }),
+                    _ => Err(WaveDecodeError::UnsupportedBitDepth { format, bits })
                 }
             },
-            WaveFormat::IeeeFloat => return Err(IoError::new(ErrorKind::Unsupported, "u8 is not capable of storing floating-point samples")),
-            _ => return Err(IoError::new(ErrorKind::Unsupported, "Unsupported format type")),
+            WaveFormat::IeeeFloat => Err(WaveDecodeError::IncompatibleSampleType { format, bits, sample_type: std::any::type_name::<Self>() }),
+            _ => Err(WaveDecodeError::UnsupportedFormat(format)),
         }
     }
+
+    fn view(data: &[u8], format: WaveFormat, bits: u16) -> Option<&[Self]> {
+        match (format, bits) {
+            // SAFETY: every bit pattern is a valid u8
+            (WaveFormat::Pcm, 8) => unsafe { view_native(data) },
+            _ => None,
+        }
+    }
+
+    fn write<W: Write>(&self, sink: &mut W, format: WaveFormat, bits: u16) -> Result<(), WaveDecodeError> {
+        write_normalized(sink, math::map_u8_to_f64(*self), format, bits)
+    }
+
+    fn from_int(val: i64, bits: u16, scaling: Scaling) -> Self {
+        (math::map_int_to_int(val, bits, 8, scaling) + 128) as u8
+    }
+
+    fn from_float(val: f64, scaling: Scaling) -> Self {
+        (math::map_f64_to_int(val, 8, scaling) + 128) as u8
+    }
+
+    fn to_int(&self, bits: u16, scaling: Scaling) -> i64 {
+        math::map_int_to_int((*self as i64) - 128, 8, bits, scaling)
+    }
+
+    fn to_float(&self, scaling: Scaling) -> f64 {
+        math::map_int_to_f64((*self as i64) - 128, 8, scaling)
+    }
 }
 impl Sample for i16 {
 
-    fn read(cursor: &mut Cursor<Vec<u8>>, format: WaveFormat, bits: u16) -> Result<Self, IoError> {
+    const BITS: u16 = 16;
+
+    fn read_slice(data: &[u8], format: WaveFormat, bits: u16, out: &mut [Self]) -> Result<usize, WaveDecodeError> {
         match format {
             WaveFormat::Pcm => match bits {
-                8 => Ok( math::map_u8_to_i16(cursor.read_u8()?)  ),
-                16 => Ok( cursor.read_i16(Endian::Little)? ),
+                8 => Ok( convert(data, out, |[x]| math::map_u8_to_i16(x)) ),
+                16 => Ok( convert(data, out, i16::from_le_bytes) ),
 
                 /* Unsupported cases */
-                b if b > 16 => Err(IoError::new(ErrorKind::Unsupported, format!("{} bits too large for i16", bits))),
-                b if (b % 8 == 0) => Err(IoError::new(ErrorKind::Unsupported, "Bits per sample must be multiple of 8")),
-                _ => Err(IoError::new(ErrorKind::Unsupported, "Unsupported bits per sample"))
+                b if b > 16 => Err(WaveDecodeError::IncompatibleSampleType { format, bits, sample_type: std::any::type_name::<Self>() }),
+                _ => Err(WaveDecodeError::UnsupportedBitDepth { format, bits })
+            },
+            WaveFormat::IeeeFloat => Err(WaveDecodeError::IncompatibleSampleType { format, bits, sample_type: std::any::type_name::<Self>() }),
+            WaveFormat::Alaw => match bits {
+                8 => Ok( convert(data, out, |[x]| g711::expand_alaw(x)) ),
+                _ => Err(WaveDecodeError::UnsupportedBitDepth { format, bits })
             },
-            WaveFormat::IeeeFloat => return Err(IoError::new(ErrorKind::Unsupported, "i16 is not capable of storing floating-point samples")),
-            _ => return Err(IoError::new(ErrorKind::Unsupported, "Unsupported format type")),
+            WaveFormat::Mulaw => match bits {
+                8 => Ok( convert(data, out, |[x]| g711::expand_mulaw(x)) ),
+                _ => Err(WaveDecodeError::UnsupportedBitDepth { format, bits })
+            },
+            _ => Err(WaveDecodeError::UnsupportedFormat(format)),
+        }
+    }
+
+    fn view(data: &[u8], format: WaveFormat, bits: u16) -> Option<&[Self]> {
+        match (format, bits) {
+            // SAFETY: every bit pattern is a valid i16
+            (WaveFormat::Pcm, 16) => unsafe { view_native(data) },
+            _ => None,
         }
     }
+
+    fn write<W: Write>(&self, sink: &mut W, format: WaveFormat, bits: u16) -> Result<(), WaveDecodeError> {
+        write_normalized(sink, math::map_i16_to_f64(*self), format, bits)
+    }
+
+    fn from_int(val: i64, bits: u16, scaling: Scaling) -> Self {
+        math::map_int_to_int(val, bits, 16, scaling) as i16
+    }
+
+    fn from_float(val: f64, scaling: Scaling) -> Self {
+        math::map_f64_to_int(val, 16, scaling) as i16
+    }
+
+    fn to_int(&self, bits: u16, scaling: Scaling) -> i64 {
+        math::map_int_to_int(*self as i64, 16, bits, scaling)
+    }
+
+    fn to_float(&self, scaling: Scaling) -> f64 {
+        math::map_int_to_f64(*self as i64, 16, scaling)
+    }
 }
 impl Sample for i32 {
 
-    fn read(cursor: &mut Cursor<Vec<u8>>, format: WaveFormat, bits: u16) -> Result<Self, IoError> {
+    const BITS: u16 = 32;
+
+    fn read_slice(data: &[u8], format: WaveFormat, bits: u16, out: &mut [Self]) -> Result<usize, WaveDecodeError> {
         match format {
             WaveFormat::Pcm => match bits {
-                8 => Ok( math::map_u8_to_i32(cursor.read_u8()?)  ),
-                16 => Ok( math::map_i16_to_i32(cursor.read_i16(Endian::Little)?) ),
-                24 => Ok( cursor.read_i24(Endian::Little)? ),
-                32 => Ok( cursor.read_i32(Endian::Little)? ),
+                8 => Ok( convert(data, out, |[x]| math::map_u8_to_i32(x)) ),
+                16 => Ok( convert(data, out, |x| math::map_i16_to_i32(i16::from_le_bytes(x))) ),
+                24 => Ok( convert(data, out, |x| math::map_i24_to_i32(i24_from_le_bytes(x))) ),
+                32 => Ok( convert(data, out, i32::from_le_bytes) ),
 
                 /* Unsupported cases */
-                b if b > 32 => Err(IoError::new(ErrorKind::Unsupported, format!("{} bits too large for i32", bits))),
-                b if (b % 8 == 0) => Err(IoError::new(ErrorKind::Unsupported, "Bits per sample must be multiple of 8")),
-                _ => Err(IoError::new(ErrorKind::Unsupported, "Unsupported bits per sample"))
+                b if b > 32 => Err(WaveDecodeError::IncompatibleSampleType { format, bits, sample_type: std::any::type_name::<Self>() }),
+                _ => Err(WaveDecodeError::UnsupportedBitDepth { format, bits })
             },
-            WaveFormat::IeeeFloat => return Err(IoError::new(ErrorKind::Unsupported, "i32 is not capable of storing floating-point samples")),
-            _ => return Err(IoError::new(ErrorKind::Unsupported, "Unsupported format type")),
+            WaveFormat::IeeeFloat => Err(WaveDecodeError::IncompatibleSampleType { format, bits, sample_type: std::any::type_name::<Self>() }),
+            WaveFormat::Alaw => match bits {
+                8 => Ok( convert(data, out, |[x]| math::map_i16_to_i32(g711::expand_alaw(x))) ),
+                _ => Err(WaveDecodeError::UnsupportedBitDepth { format, bits })
+            },
+            WaveFormat::Mulaw => match bits {
+                8 => Ok( convert(data, out, |[x]| math::map_i16_to_i32(g711::expand_mulaw(x))) ),
+                _ => Err(WaveDecodeError::UnsupportedBitDepth { format, bits })
+            },
+            _ => Err(WaveDecodeError::UnsupportedFormat(format)),
+        }
+    }
+
+    fn view(data: &[u8], format: WaveFormat, bits: u16) -> Option<&[Self]> {
+        match (format, bits) {
+            // SAFETY: every bit pattern is a valid i32
+            (WaveFormat::Pcm, 32) => unsafe { view_native(data) },
+            _ => None,
+        }
+    }
+
+    fn write<W: Write>(&self, sink: &mut W, format: WaveFormat, bits: u16) -> Result<(), WaveDecodeError> {
+        match format {
+            /* Integer PCM is mapped exactly, 24-bit samples are left-justified like all narrower ones */
+            WaveFormat::Pcm => self.write_scaled(sink, format, bits, Scaling::Asymmetric),
+            _ => write_normalized(sink, math::map_i32_to_f64(*self), format, bits),
         }
     }
+
+    fn from_int(val: i64, bits: u16, scaling: Scaling) -> Self {
+        math::map_int_to_int(val, bits, 32, scaling) as i32
+    }
+
+    fn from_float(val: f64, scaling: Scaling) -> Self {
+        math::map_f64_to_int(val, 32, scaling) as i32
+    }
+
+    fn to_int(&self, bits: u16, scaling: Scaling) -> i64 {
+        math::map_int_to_int(*self as i64, 32, bits, scaling)
+    }
+
+    fn to_float(&self, scaling: Scaling) -> f64 {
+        math::map_int_to_f64(*self as i64, 32, scaling)
+    }
 }
 impl Sample for i64 {
 
-    fn read(cursor: &mut Cursor<Vec<u8>>, format: WaveFormat, bits: u16) -> Result<Self, IoError> {
+    const BITS: u16 = 64;
+
+    fn read_slice(data: &[u8], format: WaveFormat, bits: u16, out: &mut [Self]) -> Result<usize, WaveDecodeError> {
         match format {
             WaveFormat::Pcm => match bits {
-                8 => Ok( math::map_u8_to_i64(cursor.read_u8()?)  ),
-                16 => Ok( math::map_i16_to_i64(cursor.read_i16(Endian::Little)?) ),
-                24 => Ok( math::map_i24_to_i64(cursor.read_i24(Endian::Little)?) ),
-                32 => Ok( math::map_i32_to_i64(cursor.read_i32(Endian::Little)?) ),
-                64 => Ok( cursor.read_i64(Endian::Little)? ),
+                8 => Ok( convert(data, out, |[x]| math::map_u8_to_i64(x)) ),
+                16 => Ok( convert(data, out, |x| math::map_i16_to_i64(i16::from_le_bytes(x))) ),
+                24 => Ok( convert(data, out, |x| math::map_i24_to_i64(i24_from_le_bytes(x))) ),
+                32 => Ok( convert(data, out, |x| math::map_i32_to_i64(i32::from_le_bytes(x))) ),
+                64 => Ok( convert(data, out, i64::from_le_bytes) ),
 
                 /* Unsupported cases */
-                40 | 48 | 56 => Err( IoError::new(ErrorKind::Unsupported, "Unsupported bits per sample")),
-                b if b > 64 => Err(IoError::new(ErrorKind::Unsupported, format!("{} bits too large for i64", bits))),
-                b if (b % 8 == 0) => Err(IoError::new(ErrorKind::Unsupported, "Bits per sample must be multiple of 8")),
-                _ => Err(IoError::new(ErrorKind::Unsupported, "Unsupported bits per sample"))
+                b if b > 64 => Err(WaveDecodeError::IncompatibleSampleType { format, bits, sample_type: std::any::type_name::<Self>() }),
+                _ => Err(WaveDecodeError::UnsupportedBitDepth { format, bits })
             },
-            WaveFormat::IeeeFloat => return Err(IoError::new(ErrorKind::Unsupported, "i64 is not capable of storing floating-point samples")),
-            _ => return Err(IoError::new(ErrorKind::Unsupported, "Unsupported format type")),
+            WaveFormat::IeeeFloat => Err(WaveDecodeError::IncompatibleSampleType { format, bits, sample_type: std::any::type_name::<Self>() }),
+            WaveFormat::Alaw => match bits {
+                8 => Ok( convert(data, out, |[x]| math::map_i16_to_i64(g711::expand_alaw(x))) ),
+                _ => Err(WaveDecodeError::UnsupportedBitDepth { format, bits })
+            },
+            WaveFormat::Mulaw => match bits {
+                8 => Ok( convert(data, out, |[x]| math::map_i16_to_i64(g711::expand_mulaw(x))) ),
+                _ => Err(WaveDecodeError::UnsupportedBitDepth { format, bits })
+            },
+            _ => Err(WaveDecodeError::UnsupportedFormat(format)),
+        }
+    }
+
+    fn view(data: &[u8], format: WaveFormat, bits: u16) -> Option<&[Self]> {
+        match (format, bits) {
+            // SAFETY: every bit pattern is a valid i64
+            (WaveFormat::Pcm, 64) => unsafe { view_native(data) },
+            _ => None,
+        }
+    }
+
+    fn write<W: Write>(&self, sink: &mut W, format: WaveFormat, bits: u16) -> Result<(), WaveDecodeError> {
+        match (format, bits) {
+            /* f64 cannot represent every i64, so samples of the same width are written directly */
+            (WaveFormat::Pcm, 64) => Ok( sink.write_i64(*self, Endian::Little)? ),
+            _ => write_normalized(sink, math::map_i64_to_f64(*self), format, bits),
         }
     }
+
+    fn from_int(val: i64, bits: u16, scaling: Scaling) -> Self {
+        math::map_int_to_int(val, bits, 64, scaling)
+    }
+
+    fn from_float(val: f64, scaling: Scaling) -> Self {
+        math::map_f64_to_int(val, 64, scaling)
+    }
+
+    fn to_int(&self, bits: u16, scaling: Scaling) -> i64 {
+        math::map_int_to_int(*self, 64, bits, scaling)
+    }
+
+    fn to_float(&self, scaling: Scaling) -> f64 {
+        math::map_int_to_f64(*self, 64, scaling)
+    }
 }
 impl Sample for f32 {
 
-    fn read(cursor: &mut Cursor<Vec<u8>>, format: WaveFormat, bits: u16) -> Result<Self, IoError> {
+    const BITS: u16 = 32;
+
+    fn read_slice(data: &[u8], format: WaveFormat, bits: u16, out: &mut [Self]) -> Result<usize, WaveDecodeError> {
         match format {
             WaveFormat::Pcm => match bits {
-                8 => Ok( math::map_u8_to_f32(cursor.read_u8()?)  ),
-                16 => Ok( math::map_i16_to_f32(cursor.read_i16(Endian::Little)?) ),
-                24 => { 
-                    let val = cursor.read_i24(Endian::Little)?;
-                    Ok( math::map_i24_to_f32(val) ) 
-                },
+                8 => Ok( convert(data, out, |[x]| math::map_u8_to_f32(x)) ),
+                16 => Ok( convert(data, out, |x| math::map_i16_to_f32(i16::from_le_bytes(x))) ),
+                24 => Ok( convert(data, out, |x| math::map_i24_to_f32(i24_from_le_bytes(x))) ),
                 /* WARNING: f32 is not capable of precisely representing i32 or i64, distortion may occur */
-                32 => Ok( math::map_i32_to_f32(cursor.read_i32(Endian::Little)?) ),
-                64 => Ok( math::map_i64_to_f32(cursor.read_i64(Endian::Little)?) ),
+                32 => Ok( convert(data, out, |x| math::map_i32_to_f32(i32::from_le_bytes(x))) ),
+                64 => Ok( convert(data, out, |x| math::map_i64_to_f32(i64::from_le_bytes(x))) ),
 
                 /* Unsupported cases */
-                40 | 48 | 56 => Err( IoError::new(ErrorKind::Unsupported, "Unsupported bits per sample")),
-                b if b > 64 => Err(IoError::new(ErrorKind::Unsupported, format!("{} bit int too large for f32", bits))),
-                b if (b % 8 == 0) => Err(IoError::new(ErrorKind::Unsupported, "Bits per sample must be multiple of 8")),
-                _ => Err(IoError::new(ErrorKind::Unsupported, "Unsupported bits per sample"))
+                b if b > 64 => Err(WaveDecodeError::IncompatibleSampleType { format, bits, sample_type: std::any::type_name::<Self>() }),
+                _ => Err(WaveDecodeError::UnsupportedBitDepth { format, bits })
             },
             WaveFormat::IeeeFloat => {
                 match bits {
-                    32 => Ok( cursor.read_f32(Endian::Little)? ),
-                    64 => Ok( cursor.read_f64(Endian::Little)? as f32 ),
-                    _ => Err(IoError::new(ErrorKind::Unsupported, "Only standard 32-bit and 64-bit floating-point samples are supported."))
+                    32 => Ok( convert(data, out, f32::from_le_bytes) ),
+                    64 => Ok( convert(data, out, |x| f64::from_le_bytes(x) as f32) ),
+                    _ => Err(WaveDecodeError::UnsupportedBitDepth { format, bits })
                 }
             },
-            _ => Err(IoError::new(ErrorKind::Unsupported, "Unsupported format type")),
+            WaveFormat::Alaw => match bits {
+                8 => Ok( convert(data, out, |[x]| math::map_i16_to_f32(g711::expand_alaw(x))) ),
+                _ => Err(WaveDecodeError::UnsupportedBitDepth { format, bits })
+            },
+            WaveFormat::Mulaw => match bits {
+                8 => Ok( convert(data, out, |[x]| math::map_i16_to_f32(g711::expand_mulaw(x))) ),
+                _ => Err(WaveDecodeError::UnsupportedBitDepth { format, bits })
+            },
+            _ => Err(WaveDecodeError::UnsupportedFormat(format)),
+        }
+    }
+
+    fn view(data: &[u8], format: WaveFormat, bits: u16) -> Option<&[Self]> {
+        match (format, bits) {
+            // SAFETY: every bit pattern is a valid f32
+            (WaveFormat::IeeeFloat, 32) => unsafe { view_native(data) },
+            _ => None,
         }
     }
+
+    fn write<W: Write>(&self, sink: &mut W, format: WaveFormat, bits: u16) -> Result<(), WaveDecodeError> {
+        write_normalized(sink, *self as f64, format, bits)
+    }
+
+    fn from_int(val: i64, bits: u16, scaling: Scaling) -> Self {
+        math::map_int_to_f64(val, bits, scaling) as f32
+    }
+
+    fn from_float(val: f64, _scaling: Scaling) -> Self {
+        val as f32
+    }
+
+    fn to_int(&self, bits: u16, scaling: Scaling) -> i64 {
+        math::map_f64_to_int(*self as f64, bits, scaling)
+    }
+
+    fn to_float(&self, _scaling: Scaling) -> f64 {
+        *self as f64
+    }
 }
 impl Sample for f64 {
 
-    fn read(cursor: &mut Cursor<Vec<u8>>, format: WaveFormat, bits: u16) -> Result<Self, IoError> {
+    const BITS: u16 = 64;
+
+    fn read_slice(data: &[u8], format: WaveFormat, bits: u16, out: &mut [Self]) -> Result<usize, WaveDecodeError> {
         match format {
             WaveFormat::Pcm => match bits {
-                8 => Ok( math::map_u8_to_f64(cursor.read_u8()?)  ),
-                16 => Ok( math::map_i16_to_f64(cursor.read_i16(Endian::Little)?) ),
-                24 => Ok( math::map_i24_to_f64(cursor.read_i24(Endian::Little)?) ),
-                32 => Ok( math::map_i32_to_f64(cursor.read_i32(Endian::Little)?) ),
+                8 => Ok( convert(data, out, |[x]| math::map_u8_to_f64(x)) ),
+                16 => Ok( convert(data, out, |x| math::map_i16_to_f64(i16::from_le_bytes(x))) ),
+                24 => Ok( convert(data, out, |x| math::map_i24_to_f64(i24_from_le_bytes(x))) ),
+                32 => Ok( convert(data, out, |x| math::map_i32_to_f64(i32::from_le_bytes(x))) ),
                 /* WARNING: f64 is not capable of precisely representing i64, distortion may occur */
-                64 => Ok( math::map_i64_to_f64(cursor.read_i64(Endian::Little)?) ),
+                64 => Ok( convert(data, out, |x| math::map_i64_to_f64(i64::from_le_bytes(x))) ),
 
                 /* Unsupported cases */
-                40 | 48 | 56 => Err( IoError::new(ErrorKind::Unsupported, "Unsupported bits per sample")),
-                b if b > 64 => Err(IoError::new(ErrorKind::Unsupported, format!("{} bit int too large for f64", bits))),
-                b if (b % 8 == 0) => Err(IoError::new(ErrorKind::Unsupported, "Bits per sample must be multiple of 8")),
-                _ => Err(IoError::new(ErrorKind::Unsupported, "Unsupported bits per sample"))
+                b if b > 64 => Err(WaveDecodeError::IncompatibleSampleType { format, bits, sample_type: std::any::type_name::<Self>() }),
+                _ => Err(WaveDecodeError::UnsupportedBitDepth { format, bits })
             },
             WaveFormat::IeeeFloat => {
                 match bits {
-                    32 => Ok( cursor.read_f32(Endian::Little)? as f64 ),
-                    64 => Ok( cursor.read_f64(Endian::Little)? ),
-                    _ => Err(IoError::new(ErrorKind::Unsupported, "Only standard 32-bit and 64-bit floating-point samples are supported."))
+                    32 => Ok( convert(data, out, |x| f32::from_le_bytes(x) as f64) ),
+                    64 => Ok( convert(data, out, f64::from_le_bytes) ),
+                    _ => Err(WaveDecodeError::UnsupportedBitDepth { format, bits })
                 }
             }
-            _ => return Err(IoError::new(ErrorKind::Unsupported, "Unsupported format type")),
+            WaveFormat::Alaw => match bits {
+                8 => Ok( convert(data, out, |[x]| math::map_i16_to_f64(g711::expand_alaw(x))) ),
+                _ => Err(WaveDecodeError::UnsupportedBitDepth { format, bits })
+            },
+            WaveFormat::Mulaw => match bits {
+                8 => Ok( convert(data, out, |[x]| math::map_i16_to_f64(g711::expand_mulaw(x))) ),
+                _ => Err(WaveDecodeError::UnsupportedBitDepth { format, bits })
+            },
+            _ => Err(WaveDecodeError::UnsupportedFormat(format)),
+        }
+    }
+
+    fn view(data: &[u8], format: WaveFormat, bits: u16) -> Option<&[Self]> {
+        match (format, bits) {
+            // SAFETY: every bit pattern is a valid f64
+            (WaveFormat::IeeeFloat, 64) => unsafe { view_native(data) },
+            _ => None,
+        }
+    }
+
+    fn write<W: Write>(&self, sink: &mut W, format: WaveFormat, bits: u16) -> Result<(), WaveDecodeError> {
+        write_normalized(sink, *self, format, bits)
+    }
+
+    fn from_int(val: i64, bits: u16, scaling: Scaling) -> Self {
+        math::map_int_to_f64(val, bits, scaling)
+    }
+
+    fn from_float(val: f64, _scaling: Scaling) -> Self {
+        val
+    }
+
+    fn to_int(&self, bits: u16, scaling: Scaling) -> i64 {
+        math::map_f64_to_int(*self, bits, scaling)
+    }
+
+    fn to_float(&self, _scaling: Scaling) -> f64 {
+        *self
+    }
+}
+
+/* Signed 8-bit samples, 8-bit WAVE PCM is stored unsigned and has the sign bit flipped */
+impl Sample for i8 {
+
+    const BITS: u16 = 8;
+
+    fn read_slice(data: &[u8], format: WaveFormat, bits: u16, out: &mut [Self]) -> Result<usize, WaveDecodeError> {
+        match format {
+            WaveFormat::Pcm => match bits {
+                8 => Ok( convert(data, out, |[x]| (x ^ 0x80) as i8) ),
+
+                /* Unsupported cases */
+                b if b > 8 => Err(WaveDecodeError::IncompatibleSampleType { format, bits, sample_type: std::any::type_name::<Self>() }),
+                _ => Err(WaveDecodeError::UnsupportedBitDepth { format, bits })
+            },
+            WaveFormat::IeeeFloat | WaveFormat::Alaw | WaveFormat::Mulaw => Err(WaveDecodeError::IncompatibleSampleType { format, bits, sample_type: std::any::type_name::<Self>() }),
+            _ => Err(WaveDecodeError::UnsupportedFormat(format)),
+        }
+    }
+
+    fn write<W: Write>(&self, sink: &mut W, format: WaveFormat, bits: u16) -> Result<(), WaveDecodeError> {
+        match format {
+            WaveFormat::Pcm => self.write_scaled(sink, format, bits, Scaling::Asymmetric),
+            _ => write_normalized(sink, math::map_int_to_f64(*self as i64, 8, Scaling::Asymmetric), format, bits),
+        }
+    }
+
+    fn from_int(val: i64, bits: u16, scaling: Scaling) -> Self {
+        math::map_int_to_int(val, bits, 8, scaling) as i8
+    }
+
+    fn from_float(val: f64, scaling: Scaling) -> Self {
+        math::map_f64_to_int(val, 8, scaling) as i8
+    }
+
+    fn to_int(&self, bits: u16, scaling: Scaling) -> i64 {
+        math::map_int_to_int(*self as i64, 8, bits, scaling)
+    }
+
+    fn to_float(&self, scaling: Scaling) -> f64 {
+        math::map_int_to_f64(*self as i64, 8, scaling)
+    }
+}
+
+/* Unsigned 16-bit samples with silence at 32768, as used by some devices and raw dumps */
+impl Sample for u16 {
+
+    const BITS: u16 = 16;
+
+    fn read_slice(data: &[u8], format: WaveFormat, bits: u16, out: &mut [Self]) -> Result<usize, WaveDecodeError> {
+        match format {
+            WaveFormat::Pcm => match bits {
+                8 => Ok( convert(data, out, |[x]| (math::map_u8_to_i16(x) as u16) ^ 0x8000) ),
+                16 => Ok( convert(data, out, |x| u16::from_le_bytes(x) ^ 0x8000) ),
+
+                /* Unsupported cases */
+                b if b > 16 => Err(WaveDecodeError::IncompatibleSampleType { format, bits, sample_type: std::any::type_name::<Self>() }),
+                _ => Err(WaveDecodeError::UnsupportedBitDepth { format, bits })
+            },
+            WaveFormat::IeeeFloat => Err(WaveDecodeError::IncompatibleSampleType { format, bits, sample_type: std::any::type_name::<Self>() }),
+            WaveFormat::Alaw => match bits {
+                8 => Ok( convert(data, out, |[x]| (g711::expand_alaw(x) as u16) ^ 0x8000) ),
+                _ => Err(WaveDecodeError::UnsupportedBitDepth { format, bits })
+            },
+            WaveFormat::Mulaw => match bits {
+                8 => Ok( convert(data, out, |[x]| (g711::expand_mulaw(x) as u16) ^ 0x8000) ),
+                _ => Err(WaveDecodeError::UnsupportedBitDepth { format, bits })
+            },
+            _ => Err(WaveDecodeError::UnsupportedFormat(format)),
+        }
+    }
+
+    fn write<W: Write>(&self, sink: &mut W, format: WaveFormat, bits: u16) -> Result<(), WaveDecodeError> {
+        match format {
+            WaveFormat::Pcm => self.write_scaled(sink, format, bits, Scaling::Asymmetric),
+            _ => write_normalized(sink, math::map_i16_to_f64((*self ^ 0x8000) as i16), format, bits),
+        }
+    }
+
+    fn from_int(val: i64, bits: u16, scaling: Scaling) -> Self {
+        (math::map_int_to_int(val, bits, 16, scaling) + 32768) as u16
+    }
+
+    fn from_float(val: f64, scaling: Scaling) -> Self {
+        (math::map_f64_to_int(val, 16, scaling) + 32768) as u16
+    }
+
+    fn to_int(&self, bits: u16, scaling: Scaling) -> i64 {
+        math::map_int_to_int((*self as i64) - 32768, 16, bits, scaling)
+    }
+
+    fn to_float(&self, scaling: Scaling) -> f64 {
+        math::map_int_to_f64((*self as i64) - 32768, 16, scaling)
+    }
+}
+
+/**
+ * Signed 24-bit sample stored in 3 little-endian bytes like in the file, so buffers of 24-bit
+ * PCM take no more memory than the file and can be borrowed with `WaveFile::samples_view`.
+ */
+#[derive(Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[repr(transparent)]
+pub struct I24([u8; 3]);
+
+impl I24 {
+
+    pub const MIN: I24 = I24([ 0x00, 0x00, 0x80 ]);
+    pub const MAX: I24 = I24([ 0xff, 0xff, 0x7f ]);
+
+    /**
+     * Creates a sample from `val`, values outside of the 24-bit range are clipped.
+     */
+    pub fn new(val: i32) -> I24 {
+        let bytes = val.clamp(-8388608, 8388607).to_le_bytes();
+        I24([ bytes[0], bytes[1], bytes[2] ])
+    }
+
+    pub fn from_le_bytes(bytes: [u8; 3]) -> I24 {
+        I24(bytes)
+    }
+
+    pub fn to_le_bytes(self) -> [u8; 3] {
+        self.0
+    }
+
+    pub fn get(self) -> i32 {
+        i24_from_le_bytes(self.0)
+    }
+}
+
+impl Debug for I24 {
+
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "I24({})", self.get())
+    }
+}
+
+impl From<I24> for i32 {
+
+    fn from(val: I24) -> i32 {
+        val.get()
+    }
+}
+
+impl Sample for I24 {
+
+    const BITS: u16 = 24;
+
+    fn read_slice(data: &[u8], format: WaveFormat, bits: u16, out: &mut [Self]) -> Result<usize, WaveDecodeError> {
+        // Same default policy as the other types, through the exact integer mapping of `from_int`.
+        let widen = |x: i64, bits: u16| I24::from_int(x, bits, Scaling::Asymmetric);
+        match format {
+            WaveFormat::Pcm => match bits {
+                8 => Ok( convert(data, out, |[x]| widen((x as i64) - 128, 8)) ),
+                16 => Ok( convert(data, out, |x| widen(i16::from_le_bytes(x) as i64, 16)) ),
+                24 => Ok( convert(data, out, I24) ),
+
+                /* Unsupported cases */
+                b if b > 24 => Err(WaveDecodeError::IncompatibleSampleType { format, bits, sample_type: std::any::type_name::<Self>() }),
+                _ => Err(WaveDecodeError::UnsupportedBitDepth { format, bits })
+            },
+            WaveFormat::IeeeFloat => Err(WaveDecodeError::IncompatibleSampleType { format, bits, sample_type: std::any::type_name::<Self>() }),
+            WaveFormat::Alaw => match bits {
+                8 => Ok( convert(data, out, |[x]| widen(g711::expand_alaw(x) as i64, 16)) ),
+                _ => Err(WaveDecodeError::UnsupportedBitDepth { format, bits })
+            },
+            WaveFormat::Mulaw => match bits {
+                8 => Ok( convert(data, out, |[x]| widen(g711::expand_mulaw(x) as i64, 16)) ),
+                _ => Err(WaveDecodeError::UnsupportedBitDepth { format, bits })
+            },
+            _ => Err(WaveDecodeError::UnsupportedFormat(format)),
+        }
+    }
+
+    fn view(data: &[u8], format: WaveFormat, bits: u16) -> Option<&[Self]> {
+        match (format, bits) {
+            // SAFETY: I24 is a transparent wrapper of bytes, so it has no alignment and the byte order of the host does not matter
+            (WaveFormat::Pcm, 24) => Some( unsafe { data.align_to::<I24>() }.1 ),
+            _ => None,
         }
     }
+
+    fn write<W: Write>(&self, sink: &mut W, format: WaveFormat, bits: u16) -> Result<(), WaveDecodeError> {
+        match (format, bits) {
+            (WaveFormat::Pcm, 24) => Ok( sink.write_all(&self.0)? ),
+            (WaveFormat::Pcm, _) => self.write_scaled(sink, format, bits, Scaling::Asymmetric),
+            _ => write_normalized(sink, math::map_i24_to_f64(self.get()), format, bits),
+        }
+    }
+
+    fn from_int(val: i64, bits: u16, scaling: Scaling) -> Self {
+        I24::new(math::map_int_to_int(val, bits, 24, scaling) as i32)
+    }
+
+    fn from_float(val: f64, scaling: Scaling) -> Self {
+        I24::new(math::map_f64_to_int(val, 24, scaling) as i32)
+    }
+
+    fn to_int(&self, bits: u16, scaling: Scaling) -> i64 {
+        math::map_int_to_int(self.get() as i64, 24, bits, scaling)
+    }
+
+    fn to_float(&self, scaling: Scaling) -> f64 {
+        math::map_int_to_f64(self.get() as i64, 24, scaling)
+    }
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct WaveFile {
 
     pub format: WaveFormat,
@@ -220,8 +839,19 @@ pub struct WaveFile {
     frame_size: u16,
 
     sample_data: Vec<u8>,
+    chunks: Vec<Chunk>,
+    custom_chunks: Vec<(FourCC, Arc<dyn Any + Send + Sync>)>,
 
     pub metadata: AudioMetadata,
+    pub charset: Option<CharacterSet>,
+    pub bext: Option<BroadcastExtension>,
+    pub cue_points: Vec<CuePoint>,
+    pub instrument: Option<InstrumentChunk>,
+    pub acid: Option<AcidChunk>,
+    pub ixml: Option<String>,
+    pub axml: Option<String>,
+
+    pub warnings: Vec<DecodeWarning>,   /* Non-fatal issues found while decoding */
 }
 
 impl WaveFile {
@@ -242,11 +872,73 @@ impl WaveFile {
             frame_size: 0,
 
             sample_data: vec![],
+            chunks: vec![],
+            custom_chunks: vec![],
             
             metadata: AudioMetadata::new(),
+            charset: None,
+            bext: None,
+            cue_points: vec![],
+            instrument: None,
+            acid: None,
+            ixml: None,
+            axml: None,
+
+            warnings: vec![],
         }
     }
 
+    /**
+     * Creates a file from interleaved samples, encoded with the given format and bit depth.
+     */
+    pub fn from_samples<S: Sample>(samples: &[S], channels: u16, sample_rate: u32, format: WaveFormat, bits: u16) -> Result<WaveFile, WaveDecodeError> {
+        WaveFile::encode_samples(samples, channels, sample_rate, format, bits, |x, sink| x.write(sink, format, bits))
+    }
+
+    /**
+     * Like `from_samples`, with the given scaling policy.
+     */
+    pub fn from_samples_scaled<S: Sample>(samples: &[S], channels: u16, sample_rate: u32, format: WaveFormat, bits: u16, scaling: Scaling) -> Result<WaveFile, WaveDecodeError> {
+        WaveFile::encode_samples(samples, channels, sample_rate, format, bits, |x, sink| x.write_scaled(sink, format, bits, scaling))
+    }
+
+    fn encode_samples<S, F>(samples: &[S], channels: u16, sample_rate: u32, format: WaveFormat, bits: u16, encode: F) -> Result<WaveFile, WaveDecodeError>
+    where F: Fn(&S, &mut Vec<u8>) -> Result<(), WaveDecodeError> {
+        if channels == 0 || !samples.len().is_multiple_of(channels as usize) {
+            return Err(WaveDecodeError::Unsupported(format!("{} samples cannot be split into {} channels", samples.len(), channels)));
+        }
+        if bits == 0 || !bits.is_multiple_of(8) {
+            return Err(WaveDecodeError::UnsupportedBitDepth { format, bits });
+        }
+        let frame_size = match channels.checked_mul(bits / 8) {
+            Some(x) => x,
+            None => return Err(WaveDecodeError::Unsupported(format!("Frames of {} channels at {} bits exceed the maximum frame size", channels, bits))),
+        };
+        let data_rate = match sample_rate.checked_mul(frame_size as u32) {
+            Some(x) => x,
+            None => return Err(WaveDecodeError::Unsupported(format!("Sample rate {} exceeds the maximum data rate", sample_rate))),
+        };
+
+        let mut sample_data: Vec<u8> = Vec::with_capacity(samples.len() * (bits as usize / 8));
+        for sample in samples {
+            encode(sample, &mut sample_data)?;
+        }
+
+        let mut wave_file = WaveFile::new();
+        wave_file.format = format;
+        wave_file.channels = channels;
+        wave_file.sample_rate = sample_rate;
+        wave_file.bits_per_sample = bits;
+        wave_file.frame_size = frame_size;
+        wave_file.data_rate = data_rate;
+        if format != WaveFormat::Pcm {
+            wave_file.num_of_samples = Some( (samples.len() / (channels as usize)) as u32 );
+        }
+        wave_file.sample_data = sample_data;
+
+        Ok(wave_file)
+    }
+
     #[inline(always)]
     pub fn frame_size(&self) -> u16 {
         self.frame_size
@@ -265,76 +957,654 @@ impl WaveFile {
         Duration::new((( (self.sample_data.len() as u32) / self.frame_size as u32) / self.sample_rate) as u64, 0)
     }
 
-    pub fn samples<S: Sample>(&self) -> Result<Vec<S>, IoError> {
-        let sample_data = self.sample_data.clone();
-        let sample_data_len = sample_data.len();
-        let mut cursor = Cursor::new(sample_data);
+    /**
+     * Returns all chunks in the order they appear in the file.
+     */
+    pub fn chunks(&self) -> &[Chunk] {
+        &self.chunks
+    }
+
+    /**
+     * Returns the first chunk with the given id.
+     */
+    pub fn chunk(&self, id: FourCC) -> Option<&Chunk> {
+        self.chunks.iter().find(|x| x.id == id)
+    }
 
-        let mut samples: Vec<S> = Vec::new();
-        loop {
-            if cursor.position() == sample_data_len as u64 {
-                break;
-            }
+    /**
+     * Returns the raw payload of a chunk of this file.
+     */
+    pub fn chunk_data<'a>(&'a self, chunk: &'a Chunk) -> &'a [u8] {
+        match &chunk.id.0 {
+            b"data" => &self.sample_data,
+            _ => chunk.data.bytes(),
+        }
+    }
+
+    /**
+     * Adds a chunk that is written as is. It is placed in front of the data chunk.
+     */
+    pub fn add_chunk(&mut self, chunk: Chunk) {
+        match self.chunks.iter().position(|x| &x.id.0 == b"data") {
+            Some(x) => self.chunks.insert(x, chunk),
+            None => self.chunks.push(chunk),
+        }
+    }
+
+    /**
+     * Removes all chunks with the given id that are not interpreted by this library.
+     * Returns the number of removed chunks.
+     */
+    pub fn remove_chunks(&mut self, id: FourCC) -> usize {
+        let count = self.chunks.len();
+        self.chunks.retain(|x| x.id != id || write::is_typed_chunk(x.id.as_u32()));
+        count - self.chunks.len()
+    }
+
+    /**
+     * Returns the first value of type `T` that was parsed by a registered `ChunkHandler`.
+     */
+    pub fn get_chunk<T: Any>(&self) -> Option<&T> {
+        self.custom_chunks.iter().find_map(|(_, x)| x.downcast_ref::<T>())
+    }
 
-            let sample: S = Sample::read(&mut cursor, self.format, self.bits_per_sample)?;
-            samples.push(sample);
+    /**
+     * Returns all values of type `T` that were parsed by a registered `ChunkHandler`, together with the chunk id.
+     */
+    pub fn get_chunks<T: Any>(&self) -> Vec<(FourCC, &T)> {
+        self.custom_chunks.iter()
+            .filter_map(|(id, x)| x.downcast_ref::<T>().map(|x| (*id, x)))
+            .collect()
+    }
+
+    /**
+     * Parses the common fields of the iXML chunk, if the file has one.
+     */
+    pub fn parse_ixml(&self) -> Option<IXml> {
+        self.ixml.as_deref().map(IXml::parse)
+    }
+
+    /**
+     * Borrows the sample data if it is stored as `S`, see `Sample::view`. Otherwise, e.g. if the
+     * data is not aligned for `S`, the samples are decoded into a new buffer.
+     */
+    pub fn samples_view<S: Sample>(&self) -> Result<Cow<'_, [S]>, WaveDecodeError> {
+        view_samples(&self.sample_data, self.format, self.bits_per_sample)
+    }
+
+    /**
+     * Decodes all samples at once, an incomplete sample at the end of the data is ignored.
+     */
+    pub fn samples<S: Sample>(&self) -> Result<Vec<S>, WaveDecodeError> {
+        decode_samples(&self.sample_data, self.format, self.bits_per_sample)
+    }
+
+    /**
+     * Converts integer PCM to another bit depth in place. Widening shifts the samples and is
+     * lossless, narrowing drops the least significant bits as given by `rounding`. For dithered
+     * narrowing see `reduce_bit_depth`.
+     */
+    pub fn requantize(&mut self, bits: u16, rounding: Rounding) -> Result<(), WaveDecodeError> {
+        self.requantize_with(bits, |x, from, to| math::narrow_int(x, from, to, rounding))
+    }
+
+    /**
+     * Reduces the bit depth of integer PCM or converts IEEE float to integer PCM of `bits` bits,
+     * applying the dither and noise shaping of `ditherer`. Widening is done like `requantize`.
+     */
+    pub fn reduce_bit_depth(&mut self, bits: u16, ditherer: &mut Ditherer) -> Result<(), WaveDecodeError> {
+        let channels = (self.channels as usize).max(1);
+        let mut index: usize = 0;
+        match self.format {
+            WaveFormat::Pcm => self.requantize_with(bits, |x, from, to| {
+                let val = (x as f64) / ((1_u64 << (from - to)) as f64);
+                index += 1;
+                ditherer.quantize(val, (index - 1) % channels, to)
+            }),
+            WaveFormat::IeeeFloat => {
+                if !matches!(bits, 8 | 16 | 24 | 32 | 64) {
+                    return Err(WaveDecodeError::UnsupportedBitDepth { format: WaveFormat::Pcm, bits });
+                }
+
+                let scale = (1_u64 << (bits - 1)) as f64;
+                let samples: Vec<i64> = self.samples::<f64>()?.iter().enumerate()
+                    .map(|(i, x)| math::widen_int(ditherer.quantize(x * scale, i % channels, bits), bits, 64))
+                    .collect();
+
+                let wave_file = WaveFile::from_samples_scaled(&samples, self.channels, self.sample_rate, WaveFormat::Pcm, bits, Scaling::Shift)?;
+                self.format = WaveFormat::Pcm;
+                self.num_of_samples = None;
+                self.sample_data = wave_file.sample_data;
+                self.bits_per_sample = bits;
+                self.frame_size = wave_file.frame_size;
+                self.data_rate = wave_file.data_rate;
+                self.valid_bps = None;
+
+                Ok(())
+            },
+            format => Err(WaveDecodeError::UnsupportedFormat(format)),
+        }
+    }
+
+    /**
+     * Decodes the samples as 64-bit integers, narrows them with `narrow` if needed and encodes
+     * them with the new bit depth.
+     */
+    fn requantize_with<F>(&mut self, bits: u16, mut narrow: F) -> Result<(), WaveDecodeError>
+    where F: FnMut(i64, u16, u16) -> i64 {
+        let format = self.format;
+        if format != WaveFormat::Pcm {
+            return Err(WaveDecodeError::UnsupportedFormat(format));
+        }
+        if !matches!(bits, 8 | 16 | 24 | 32 | 64) {
+            return Err(WaveDecodeError::UnsupportedBitDepth { format, bits });
+        }
+
+        let from = self.bits_per_sample;
+        let mut samples: Vec<i64> = self.samples_scaled(Scaling::Shift)?;
+        if bits < from {
+            // Samples are at 64 bits, the narrowed value is moved back there so the final shift is exact.
+            for sample in samples.iter_mut() {
+                *sample = math::widen_int(narrow(*sample >> (64 - from), from, bits), bits, 64);
+            }
         }
 
+        let wave_file = WaveFile::from_samples_scaled(&samples, self.channels, self.sample_rate, format, bits, Scaling::Shift)?;
+        self.sample_data = wave_file.sample_data;
+        self.bits_per_sample = bits;
+        self.frame_size = wave_file.frame_size;
+        self.data_rate = wave_file.data_rate;
+        self.valid_bps = self.valid_bps.map(|x| x.min(bits));
+
+        Ok(())
+    }
+
+    /**
+     * Like `samples`, with the conversion given by `options`. With the default options the
+     * result is the same as of `samples`.
+     */
+    pub fn samples_with<S: Sample>(&self, options: SampleOptions) -> Result<Vec<S>, WaveDecodeError> {
+        let format = self.format;
+        let from = self.bits_per_sample;
+        let narrowing = match S::read_slice(&[], format, from, &mut []) {
+            Err(WaveDecodeError::IncompatibleSampleType { .. }) if options.narrowing != Narrowing::Refuse => options.narrowing,
+            _ => return match options.scaling {
+                Scaling::Asymmetric => self.samples(),
+                scaling => self.samples_scaled(scaling),
+            },
+        };
+
+        // Integer PCM is decoded at 64 bits and float as f64, both are then quantized to the width of `S`.
+        let to = S::BITS;
+        let (min, max) = math::int_limits(to);
+        let channels = (self.channels as usize).max(1);
+        let mut ditherer = match narrowing {
+            Narrowing::Dither(kind, seed) => Some(Ditherer::new(kind, NoiseShaping::None, seed)),
+            _ => None,
+        };
+        let mut quantize = |i: usize, val: f64| match ditherer.as_mut() {
+            Some(x) => x.quantize(val, i % channels, to),
+            None => (val + 0.5).floor() as i64,
+        };
+
+        let samples: Vec<i64> = match format {
+            WaveFormat::Pcm => self.samples_scaled::<i64>(Scaling::Shift)?.into_iter()
+                .map(|x| (x >> (64 - from), math::int_limits(from)))
+                .enumerate()
+                .map(|(i, (x, (from_min, from_max)))| {
+                    // Exact ratio of the limits, asymmetric scaling maps MAX onto MAX. Otherwise it is a shift.
+                    let (num, den) = match (options.scaling, x > 0) {
+                        (Scaling::Asymmetric, true) => (max as i128, from_max as i128),
+                        _ => (-(min as i128), -(from_min as i128)),
+                    };
+                    let val = (x as i128) * num;
+                    match narrowing {
+                        Narrowing::Truncate => val.div_euclid(den) as i64,
+                        Narrowing::Dither(..) => quantize(i, (val as f64) / (den as f64)),
+                        _ => (val + den / 2).div_euclid(den) as i64,
+                    }
+                })
+                .collect(),
+            _ => self.samples::<f64>()?.into_iter()
+                .enumerate()
+                .map(|(i, x)| {
+                    let val = x.clamp(-1.0, 1.0) * match (options.scaling, x < 0.0) {
+                        (Scaling::Asymmetric, false) => max as f64,
+                        _ => (min as f64).abs(),
+                    };
+                    match narrowing {
+                        Narrowing::Truncate => val.floor() as i64,
+                        _ => quantize(i, val),
+                    }
+                })
+                .collect(),
+        };
+
+        Ok(samples.into_iter().map(|x| S::from_int(x.clamp(min, max), to, Scaling::Shift)).collect())
+    }
+
+    /**
+     * Like `samples`, with the given scaling policy.
+     */
+    pub fn samples_scaled<S: Sample>(&self, scaling: Scaling) -> Result<Vec<S>, WaveDecodeError> {
+        let sample_size = (self.bits_per_sample as usize / 8).max(1);
+        let mut samples: Vec<S> = vec![S::default(); self.sample_data.len() / sample_size];
+
+        let len = S::read_slice_scaled(&self.sample_data, self.format, self.bits_per_sample, scaling, &mut samples)?;
+        samples.truncate(len);
+
         Ok(samples)
     }
 }
 
+pub(crate) fn decode_samples<S: Sample>(data: &[u8], format: WaveFormat, bits: u16) -> Result<Vec<S>, WaveDecodeError> {
+    let sample_size = (bits as usize / 8).max(1);
+    let mut samples: Vec<S> = vec![S::default(); data.len() / sample_size];
+
+    let len = S::read_slice(data, format, bits, &mut samples)?;
+    samples.truncate(len);
+
+    Ok(samples)
+}
+
+pub(crate) fn view_samples<S: Sample>(data: &[u8], format: WaveFormat, bits: u16) -> Result<Cow<'_, [S]>, WaveDecodeError> {
+    match S::view(data, format, bits) {
+        Some(x) => Ok(Cow::Borrowed(x)),
+        None => decode_samples(data, format, bits).map(Cow::Owned),
+    }
+}
+
+/**
+ * Four character code as used for chunk ids and INFO field ids.
+ */
+#[derive(PartialEq, Eq, Hash, Clone, Copy)]
+pub struct FourCC(pub [u8; 4]);
+
+impl FourCC {
+
+    /**
+     * Creates a four character code from an id read as little-endian `u32`.
+     */
+    pub const fn from_u32(val: u32) -> FourCC {
+        FourCC(val.to_le_bytes())
+    }
+
+    pub const fn as_u32(&self) -> u32 {
+        u32::from_le_bytes(self.0)
+    }
+}
+
+impl From<u32> for FourCC {
+
+    fn from(val: u32) -> FourCC {
+        FourCC::from_u32(val)
+    }
+}
+impl From<&[u8; 4]> for FourCC {
+
+    fn from(val: &[u8; 4]) -> FourCC {
+        FourCC(*val)
+    }
+}
+impl Display for FourCC {
+
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        for c in self.0 {
+            match c.is_ascii_graphic() || c == b' ' {
+                true => write!(f, "{}", c as char)?,
+                false => write!(f, "\\x{:02x}", c)?,
+            }
+        }
+        Ok(())
+    }
+}
+impl Debug for FourCC {
+
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "FourCC(\"{}\")", self)
+    }
+}
+
+/**
+ * Text metadata of the LIST INFO chunk. Earlier versions stored INAM in `name` and IPRD in
+ * `title`. INAM is now found in `title` and IPRD in `album`, `name()` is kept as deprecated accessor.
+ */
 #[derive(Clone, Debug)]
 pub struct AudioMetadata {
-    pub album: Option<String>,
-    pub artist: Option<String>,
-    pub copyright: Option<String>,
-    pub date: Option<String>,
-    pub genre: Option<String>,
-    pub keywords: Option<String>,
-    pub name: Option<String>,
-    pub title: Option<String>,
-    pub comments: Option<String>,
-    pub description: Option<String>,
+    pub archival_location: Option<String>,      /* IARL */
+    pub artist: Option<String>,                 /* IART */
+    pub commissioned: Option<String>,           /* ICMS */
+    pub comments: Option<String>,               /* ICMT */
+    pub copyright: Option<String>,              /* ICOP */
+    pub date: Option<String>,                   /* ICRD */
+    pub cropped: Option<String>,                /* ICRP */
+    pub dimensions: Option<String>,             /* IDIM */
+    pub digitization_date: Option<String>,      /* IDIT */
+    pub dots_per_inch: Option<String>,          /* IDPI */
+    pub engineer: Option<String>,               /* IENG */
+    pub genre: Option<String>,                  /* IGNR */
+    pub keywords: Option<String>,               /* IKEY */
+    pub lightness: Option<String>,              /* ILGT */
+    pub language: Option<String>,               /* ILNG */
+    pub medium: Option<String>,                 /* IMED */
+    pub title: Option<String>,                  /* INAM */
+    pub palette: Option<String>,                /* IPLT */
+    pub album: Option<String>,                  /* IPRD */
+    pub description: Option<String>,            /* ISBJ */
+    pub encoder: Option<String>,                /* ISFT */
+    pub sharpness: Option<String>,              /* ISHP */
+    pub smpte_time: Option<String>,             /* ISMP */
+    pub source: Option<String>,                 /* ISRC */
+    pub source_form: Option<String>,            /* ISRF */
+    pub technician: Option<String>,             /* ITCH */
+    pub track: Option<String>,                  /* ITRK */
+    pub part: Option<String>,                   /* IPRT, some tools store the track number here instead of ITRK */
 
-    pub encoder: Option<String>,
+    /* All INFO fields in the order they appeared in the file, including unknown ones */
+    pub info: Vec<(FourCC, String)>,
 }
 
 impl AudioMetadata {
 
     pub fn new() -> AudioMetadata {
         AudioMetadata {
-            album: None,
+            archival_location: None,
             artist: None,
+            commissioned: None,
+            comments: None,
             copyright: None,
             date: None,
+            cropped: None,
+            dimensions: None,
+            digitization_date: None,
+            dots_per_inch: None,
+            engineer: None,
             genre: None,
             keywords: None,
-            name: None,
+            lightness: None,
+            language: None,
+            medium: None,
             title: None,
-            comments: None,
+            palette: None,
+            album: None,
             description: None,
-
             encoder: None,
+            sharpness: None,
+            smpte_time: None,
+            source: None,
+            source_form: None,
+            technician: None,
+            track: None,
+            part: None,
+
+            info: Vec::new(),
         }
     }
+
+    /**
+     * Title of the file (INAM), formerly the `name` field.
+     */
+    #[deprecated(note = "INAM is stored in `title`, IPRD moved from `title` to `album`")]
+    pub fn name(&self) -> Option<&str> {
+        self.title.as_deref()
+    }
+
+    /**
+     * Returns the typed field that belongs to the INFO id, `None` if the id is not a known field.
+     */
+    pub fn field(&self, id: FourCC) -> Option<&Option<String>> {
+        let field = match &id.0 {
+            b"IARL" => &self.archival_location, b"IART" => &self.artist, b"ICMS" => &self.commissioned,
+            b"ICMT" => &self.comments, b"ICOP" => &self.copyright, b"ICRD" => &self.date,
+            b"ICRP" => &self.cropped, b"IDIM" => &self.dimensions, b"IDIT" => &self.digitization_date,
+            b"IDPI" => &self.dots_per_inch, b"IENG" => &self.engineer, b"IGNR" => &self.genre,
+            b"IKEY" => &self.keywords, b"ILGT" => &self.lightness, b"ILNG" => &self.language,
+            b"IMED" => &self.medium, b"INAM" => &self.title, b"IPLT" => &self.palette,
+            b"IPRD" => &self.album, b"ISBJ" => &self.description, b"ISFT" => &self.encoder,
+            b"ISHP" => &self.sharpness, b"ISMP" => &self.smpte_time, b"ISRC" => &self.source,
+            b"ISRF" => &self.source_form, b"ITCH" => &self.technician, b"ITRK" => &self.track,
+            b"IPRT" => &self.part,
+            _ => return None,
+        };
+        Some(field)
+    }
+
+    /**
+     * Mutable counterpart of `field`.
+     */
+    pub fn field_mut(&mut self, id: FourCC) -> Option<&mut Option<String>> {
+        let field = match &id.0 {
+            b"IARL" => &mut self.archival_location, b"IART" => &mut self.artist, b"ICMS" => &mut self.commissioned,
+            b"ICMT" => &mut self.comments, b"ICOP" => &mut self.copyright, b"ICRD" => &mut self.date,
+            b"ICRP" => &mut self.cropped, b"IDIM" => &mut self.dimensions, b"IDIT" => &mut self.digitization_date,
+            b"IDPI" => &mut self.dots_per_inch, b"IENG" => &mut self.engineer, b"IGNR" => &mut self.genre,
+            b"IKEY" => &mut self.keywords, b"ILGT" => &mut self.lightness, b"ILNG" => &mut self.language,
+            b"IMED" => &mut self.medium, b"INAM" => &mut self.title, b"IPLT" => &mut self.palette,
+            b"IPRD" => &mut self.album, b"ISBJ" => &mut self.description, b"ISFT" => &mut self.encoder,
+            b"ISHP" => &mut self.sharpness, b"ISMP" => &mut self.smpte_time, b"ISRC" => &mut self.source,
+            b"ISRF" => &mut self.source_form, b"ITCH" => &mut self.technician, b"ITRK" => &mut self.track,
+            b"IPRT" => &mut self.part,
+            _ => return None,
+        };
+        Some(field)
+    }
+
+    /**
+     * Returns the INFO fields that should be written. Fields keep the order of `info`, values of
+     * known fields are taken from the typed fields, so edits of those take precedence. Typed fields
+     * that have no entry in `info` are appended.
+     */
+    pub fn info_entries(&self) -> Vec<(FourCC, String)> {
+        let mut entries: Vec<(FourCC, String)> = Vec::new();
+        let mut emitted: Vec<FourCC> = Vec::new();
+
+        for (id, value) in &self.info {
+            match self.field(*id) {
+                Some(field) => {
+                    // A typed field is only emitted once, even if the file contained duplicates.
+                    if emitted.contains(id) {
+                        continue;
+                    }
+                    emitted.push(*id);
+                    if let Some(x) = field {
+                        entries.push((*id, x.clone()));
+                    }
+                },
+                None => entries.push((*id, value.clone())),
+            }
+        }
+
+        for id in INFO_IDS {
+            let id = FourCC(*id);
+            if emitted.contains(&id) {
+                continue;
+            }
+            if let Some(Some(x)) = self.field(id) {
+                entries.push((id, x.clone()));
+            }
+        }
+
+        entries
+    }
 }
 
+/* Known INFO ids in the order they are written when creating new metadata */
+const INFO_IDS: &[[u8; 4]] = &[
+    *b"INAM", *b"IART", *b"IPRD", *b"ITRK", *b"IGNR", *b"ICRD", *b"ICMT", *b"ICOP", *b"ISBJ", *b"IKEY",
+    *b"IENG", *b"ITCH", *b"ISFT", *b"ISRC", *b"ISRF", *b"IMED", *b"IARL", *b"ICMS", *b"ILNG", *b"ISMP",
+    *b"IDIT", *b"ICRP", *b"IDIM", *b"IDPI", *b"ILGT", *b"IPLT", *b"ISHP", *b"IPRT",
+];
+
 #[derive(Debug, Clone)]
-pub struct WaveDecodeError {
-    pub message: String
+pub enum WaveDecodeError {
+    /* Source does not start with a RIFF WAVE header */
+    InvalidHeader,
+    /* Size in the RIFF header does not match the size of the source */
+    SizeMismatch { declared: u64, actual: u64 },
+    /* A mandatory chunk is missing */
+    MissingChunk(FourCC),
+    /* The format tag of the fmt chunk is unknown */
+    UnknownFormat(u16),
+    /* The format is known, but decoding it is not supported */
+    UnsupportedFormat(WaveFormat),
+    /* The bit depth is not supported for this format */
+    UnsupportedBitDepth { format: WaveFormat, bits: u16 },
+    /* The requested sample type cannot represent samples of this format and bit depth */
+    IncompatibleSampleType { format: WaveFormat, bits: u16, sample_type: &'static str },
+    /* A chunk has invalid contents, `offset` is the offset of the chunk header */
+    InvalidChunk { id: FourCC, offset: u64, message: String },
+    /* The source ended unexpectedly within the chunk at `offset` */
+    Truncated { offset: u64 },
+    /* The operation is not supported for this file */
+    Unsupported(String),
+    /* Any other IO error of the source */
+    Io(Arc<IoError>),
+}
+
+impl WaveDecodeError {
+
+    /**
+     * Creates an `InvalidChunk` error, the offset is filled in by the reader.
+     */
+    pub(crate) fn invalid_chunk(id: u32, message: String) -> WaveDecodeError {
+        WaveDecodeError::InvalidChunk { id: FourCC::from_u32(id), offset: 0, message }
+    }
+
+    /**
+     * Adds the offset of the chunk in which the error occurred.
+     */
+    pub(crate) fn at(self, chunk_offset: u64) -> WaveDecodeError {
+        match self {
+            WaveDecodeError::InvalidChunk { id, message, .. } => WaveDecodeError::InvalidChunk { id, offset: chunk_offset, message },
+            WaveDecodeError::Truncated { .. } => WaveDecodeError::Truncated { offset: chunk_offset },
+            WaveDecodeError::Io(e) if e.kind() == ErrorKind::UnexpectedEof => WaveDecodeError::Truncated { offset: chunk_offset },
+            x => x,
+        }
+    }
 }
 
 impl Display for WaveDecodeError {
 
     fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
-        write!(f, "Wave decode error ({})", self.message)
+        match self {
+            WaveDecodeError::InvalidHeader => write!(f, "Wave decode error (Source has invalid RIFF WAVE header)"),
+            WaveDecodeError::SizeMismatch { declared, actual } => 
+                write!(f, "Wave decode error (Bytestream size ({} B) is not equal to specified file size in riff header ({} B))", actual, declared),
+            WaveDecodeError::MissingChunk(id) => write!(f, "Wave decode error (Mandatory chunk '{}' is missing)", id),
+            WaveDecodeError::UnknownFormat(x) => write!(f, "Wave decode error (Unknown wave format 0x{:04x})", x),
+            WaveDecodeError::UnsupportedFormat(x) => write!(f, "Wave decode error (Unsupported format {})", x.stringify()),
+            WaveDecodeError::UnsupportedBitDepth { format, bits } => 
+                write!(f, "Wave decode error ({} bits per sample are not supported for {})", bits, format.stringify()),
+            WaveDecodeError::IncompatibleSampleType { format, bits, sample_type } => 
+                write!(f, "Wave decode error ({} is not capable of storing {}-bit {} samples)", sample_type, bits, format.stringify()),
+            WaveDecodeError::InvalidChunk { id, offset, message } => write!(f, "Wave decode error (Invalid chunk '{}' at {}: {})", id, offset, message),
+            WaveDecodeError::Truncated { offset } => write!(f, "Wave decode error (Source is truncated in chunk at {})", offset),
+            WaveDecodeError::Unsupported(x) => write!(f, "Wave decode error ({})", x),
+            WaveDecodeError::Io(e) => write!(f, "Wave decode error (Internal IO error: {})", e),
+        }
+    }
+}
+/* IO errors are not comparable, they are considered equal if they are of the same kind */
+impl PartialEq for WaveDecodeError {
+
+    fn eq(&self, other: &WaveDecodeError) -> bool {
+        use WaveDecodeError::*;
+        match (self, other) {
+            (InvalidHeader, InvalidHeader) => true,
+            (SizeMismatch { declared: a, actual: b }, SizeMismatch { declared: x, actual: y }) => a == x && b == y,
+            (MissingChunk(a), MissingChunk(x)) => a == x,
+            (UnknownFormat(a), UnknownFormat(x)) => a == x,
+            (UnsupportedFormat(a), UnsupportedFormat(x)) => a == x,
+            (UnsupportedBitDepth { format: a, bits: b }, UnsupportedBitDepth { format: x, bits: y }) => a == x && b == y,
+            (IncompatibleSampleType { format: a, bits: b, sample_type: c }, IncompatibleSampleType { format: x, bits: y, sample_type: z }) =>
+                a == x && b == y && c == z,
+            (InvalidChunk { id: a, offset: b, message: c }, InvalidChunk { id: x, offset: y, message: z }) => a == x && b == y && c == z,
+            (Truncated { offset: a }, Truncated { offset: x }) => a == x,
+            (Unsupported(a), Unsupported(x)) => a == x,
+            (Io(a), Io(x)) => a.kind() == x.kind(),
+            _ => false,
+        }
     }
 }
 impl From<IoError> for WaveDecodeError { 
 
     fn from(v: IoError) -> WaveDecodeError {
-        WaveDecodeError { message: format!("Internal IO error: {}", v) }
+        WaveDecodeError::Io(Arc::new(v))
     }    
 }
-impl Error for WaveDecodeError {}
\ No newline at end of file
+impl Error for WaveDecodeError {
+
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            WaveDecodeError::Io(e) => Some(e.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+/**
+ * Non-fatal issues found while decoding. They are collected in `WaveFile::warnings` and
+ * can additionally be forwarded to a callback registered at the `WaveReader`.
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub enum DecodeWarning {
+    /* A chunk that is not interpreted by this library, it is kept as raw chunk */
+    UnknownChunk { id: FourCC, offset: u64 },
+    /* A LIST chunk of a type other than INFO, it is kept as raw chunk */
+    UnknownListType { list_type: FourCC, offset: u64 },
+    /* Bytes behind the last chunk that are too short to form a chunk header */
+    TrailingBytes { offset: u64, len: u64 },
+    /* Size in the RIFF header does not match the size of the source, the actual size is used */
+    SizeMismatch { declared: u64, actual: u64 },
+    /* The data chunk states a size that is not available, only `actual` bytes were read */
+    DataClamped { offset: u64, declared: u32, actual: u32 },
+    /* The chunk at `offset` exceeds the source, decoding stopped there */
+    TruncatedChunk { id: FourCC, offset: u64 },
+    /* A mandatory chunk is missing */
+    MissingChunk(FourCC),
+}
+
+impl DecodeWarning {
+
+    /**
+     * Offset in the source at which the issue was found, if it relates to a location.
+     */
+    pub fn offset(&self) -> Option<u64> {
+        match self {
+            DecodeWarning::UnknownChunk { offset, .. } => Some(*offset),
+            DecodeWarning::UnknownListType { offset, .. } => Some(*offset),
+            DecodeWarning::TrailingBytes { offset, .. } => Some(*offset),
+            DecodeWarning::DataClamped { offset, .. } => Some(*offset),
+            DecodeWarning::TruncatedChunk { offset, .. } => Some(*offset),
+            DecodeWarning::SizeMismatch { .. } | DecodeWarning::MissingChunk(_) => None,
+        }
+    }
+
+    pub(crate) fn offset_mut(&mut self) -> Option<&mut u64> {
+        match self {
+            DecodeWarning::UnknownChunk { offset, .. } => Some(offset),
+            DecodeWarning::UnknownListType { offset, .. } => Some(offset),
+            DecodeWarning::TrailingBytes { offset, .. } => Some(offset),
+            DecodeWarning::DataClamped { offset, .. } => Some(offset),
+            DecodeWarning::TruncatedChunk { offset, .. } => Some(offset),
+            DecodeWarning::SizeMismatch { .. } | DecodeWarning::MissingChunk(_) => None,
+        }
+    }
+}
+
+impl Display for DecodeWarning {
+
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            DecodeWarning::UnknownChunk { id, offset } => write!(f, "Keeping unknown chunk '{}' at {} as raw chunk", id, offset),
+            DecodeWarning::UnknownListType { list_type, offset } => write!(f, "Keeping LIST chunk of type '{}' at {} as raw chunk", list_type, offset),
+            DecodeWarning::TrailingBytes { offset, len } => write!(f, "Ignoring {} trailing bytes at {}", len, offset),
+            DecodeWarning::SizeMismatch { declared, actual } => 
+                write!(f, "Bytestream size ({} B) is not equal to specified file size in riff header ({} B)", actual, declared),
+            DecodeWarning::DataClamped { offset, declared, actual } => 
+                write!(f, "Data chunk at {} states {} B, only {} B are used", offset, declared, actual),
+            DecodeWarning::TruncatedChunk { id, offset } => write!(f, "Chunk '{}' at {} exceeds the source", id, offset),
+            DecodeWarning::MissingChunk(id) => write!(f, "Mandatory chunk '{}' is missing", id),
+        }
+    }
+}
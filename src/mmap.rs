@@ -0,0 +1,95 @@
+use crate::{decode_samples, view_samples, Sample, WaveDecodeError, WaveFile};
+use crate::read::{DecodeOptions, Decoder};
+
+use memmap2::Mmap;
+
+use std::borrow::Cow;
+use std::fs::File;
+use std::ops::Range;
+use std::path::Path;
+use std::sync::Arc;
+
+/**
+ * A WAVE file read through a memory mapping. The chunks are decoded as usual, the sample data
+ * is not copied but borrowed from the mapping, so only the parts that are accessed are loaded.
+ */
+pub struct MappedWaveFile {
+    map: Arc<Mmap>,
+    wave_file: WaveFile,            /* Everything but the sample data */
+    data: Range<usize>,             /* Sample data within the mapping */
+}
+
+impl MappedWaveFile {
+
+    /**
+     * Maps the file at `path` and decodes its chunks.
+     *
+     * # Safety
+     *
+     * The file must not be modified or truncated while it is mapped, e.g. by another process.
+     * Modifications change the borrowed samples, accessing truncated parts terminates the process.
+     */
+    pub unsafe fn open<P: AsRef<Path>>(path: P) -> Result<MappedWaveFile, WaveDecodeError> {
+        MappedWaveFile::open_with_options(path, DecodeOptions::new())
+    }
+
+    /**
+     * Like `open`, with the given decode options.
+     *
+     * # Safety
+     *
+     * See `open`.
+     */
+    pub unsafe fn open_with_options<P: AsRef<Path>>(path: P, options: DecodeOptions) -> Result<MappedWaveFile, WaveDecodeError> {
+        let file = File::open(path)?;
+        let map = Arc::new(Mmap::map(&file)?);
+        let (wave_file, data) = Decoder::new(options).decode_in_place(map.clone())?;
+
+        Ok(MappedWaveFile { map, wave_file, data })
+    }
+
+    /**
+     * Returns format and metadata of the file. Its sample data is empty, see `sample_data`.
+     */
+    pub fn wave_file(&self) -> &WaveFile {
+        &self.wave_file
+    }
+
+    /**
+     * Returns the payload of the data chunk as stored in the file.
+     */
+    pub fn sample_data(&self) -> &[u8] {
+        &self.map[self.data.clone()]
+    }
+
+    pub fn num_of_samples(&self) -> u32 {
+        match (self.wave_file.num_of_samples, self.wave_file.frame_size) {
+            (Some(x), _) => x,
+            (None, 0) => 0,
+            (None, x) => ((self.data.len() / x as usize) * self.wave_file.channels as usize) as u32,
+        }
+    }
+
+    /**
+     * Borrows the samples from the mapping if they are stored as `S`, see `WaveFile::samples_view`.
+     */
+    pub fn samples_view<S: Sample>(&self) -> Result<Cow<'_, [S]>, WaveDecodeError> {
+        view_samples(self.sample_data(), self.wave_file.format, self.wave_file.bits_per_sample)
+    }
+
+    pub fn samples<S: Sample>(&self) -> Result<Vec<S>, WaveDecodeError> {
+        decode_samples(self.sample_data(), self.wave_file.format, self.wave_file.bits_per_sample)
+    }
+
+    /**
+     * Copies the sample data into memory and unmaps the file.
+     */
+    pub fn into_wave_file(self) -> WaveFile {
+        let mut wave_file = self.wave_file;
+        for chunk in wave_file.chunks.iter_mut() {
+            chunk.detach();
+        }
+        wave_file.sample_data = self.map[self.data].to_vec();
+        wave_file
+    }
+}
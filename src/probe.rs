@@ -0,0 +1,93 @@
+use crate::{WaveDecodeError, WaveFile};
+use crate::{au, caf, read};
+use crate::read::DecodeOptions;
+
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+const WAVE64_GUID: [u8; 16] = [ 0x72, 0x69, 0x66, 0x66, 0x2e, 0x91, 0xcf, 0x11, 0xa5, 0xd6, 0x28, 0xdb, 0x04, 0xc1, 0x00, 0x00 ];
+
+/**
+ * Containers recognized by their magic bytes. Not all of them can be decoded, see `is_supported`.
+ */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Container {
+    Wave,
+    Rifx,                   /* Big-endian RIFF WAVE */
+    Rf64,
+    Bw64,
+    Wave64,
+    Aiff,
+    Aifc,
+    Caf,
+    Au,
+}
+
+impl Container {
+
+    pub fn stringify(&self) -> String {
+        (match *self {
+            Container::Wave => "RIFF WAVE", Container::Rifx => "RIFX WAVE", Container::Rf64 => "RF64",
+            Container::Bw64 => "BW64", Container::Wave64 => "Wave64", Container::Aiff => "AIFF",
+            Container::Aifc => "AIFF-C", Container::Caf => "CAF", Container::Au => "Sun/NeXT AU",
+        }).to_string()
+    }
+
+    /**
+     * Returns true if a decoder for this container is available.
+     */
+    pub fn is_supported(&self) -> bool {
+        matches!(self, Container::Wave | Container::Caf | Container::Au)
+    }
+}
+
+/**
+ * Detects the container from the first bytes of a file, at least 16 bytes are required
+ * to recognize all containers.
+ */
+pub fn detect(header: &[u8]) -> Option<Container> {
+    if header.get(0..16) == Some(&WAVE64_GUID[..]) {
+        return Some(Container::Wave64);
+    }
+
+    let magic: [u8; 4] = header.get(0..4)?.try_into().ok()?;
+    let form: Option<&[u8]> = header.get(8..12);
+    match &magic {
+        b"RIFF" if form == Some(b"WAVE") => Some(Container::Wave),
+        b"RIFX" if form == Some(b"WAVE") => Some(Container::Rifx),
+        b"RF64" if form == Some(b"WAVE") => Some(Container::Rf64),
+        b"BW64" if form == Some(b"WAVE") => Some(Container::Bw64),
+        b"FORM" if form == Some(b"AIFF") => Some(Container::Aiff),
+        b"FORM" if form == Some(b"AIFC") => Some(Container::Aifc),
+        b"caff" => Some(Container::Caf),
+        b".snd" => Some(Container::Au),
+        _ => None,
+    }
+}
+
+/**
+ * Decodes `source` with the decoder matching its container. Only RIFF WAVE, CAF and AU files
+ * can be decoded, RIFX, RF64, BW64, Wave64, AIFF and AIFF-C are recognized but result in
+ * `WaveDecodeError::Unsupported`.
+ */
+pub fn probe<R: Read>(mut source: R) -> Result<WaveFile, WaveDecodeError> {
+    let mut buf: Vec<u8> = Vec::new();
+    source.read_to_end(&mut buf)?;
+
+    // The decoders work on the buffer that was already read instead of reading the source again.
+    match detect(&buf) {
+        Some(Container::Wave) => read::decode_buffer(buf, DecodeOptions::new()),
+        Some(Container::Caf) => caf::decode_bytes(&buf).map(|x| x.wave_file),
+        Some(Container::Au) => au::decode_bytes(&buf),
+        Some(x) => Err(WaveDecodeError::Unsupported(format!("{} files are not supported", x.stringify()))),
+        None => Err(WaveDecodeError::InvalidHeader),
+    }
+}
+
+/**
+ * Opens and decodes the file at `path`, see `probe`.
+ */
+pub fn open<P: AsRef<Path>>(path: P) -> Result<WaveFile, WaveDecodeError> {
+    probe(File::open(path)?)
+}
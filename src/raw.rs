@@ -0,0 +1,176 @@
+use crate::{DecodeWarning, WaveDecodeError, WaveFile, WaveFormat};
+use crate::util::exts::Endian;
+
+use std::io::{Error as IoError, ErrorKind, Read, Write};
+
+/**
+ * Layout of headerless sample data. As raw dumps carry no information about themselves,
+ * everything has to be known in advance.
+ */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RawPcmSpec {
+    pub format: WaveFormat,         /* Pcm, IeeeFloat, Alaw or Mulaw */
+    pub channels: u16,
+    pub sample_rate: u32,
+    pub bits_per_sample: u16,
+    pub endian: Endian,
+    pub signed: bool,               /* Only relevant for integer PCM */
+}
+
+impl RawPcmSpec {
+
+    /**
+     * Creates a little-endian spec with the signedness WAVE uses, i.e. unsigned 8-bit and signed wider samples.
+     */
+    pub fn new(format: WaveFormat, channels: u16, sample_rate: u32, bits_per_sample: u16) -> RawPcmSpec {
+        RawPcmSpec { format, channels, sample_rate, bits_per_sample, endian: Endian::Little, signed: bits_per_sample > 8 }
+    }
+
+    /**
+     * Creates the spec of the sample data of `wave_file` as it is stored in the file.
+     */
+    pub fn from_wave_file(wave_file: &WaveFile) -> RawPcmSpec {
+        RawPcmSpec::new(wave_file.format, wave_file.channels, wave_file.sample_rate, wave_file.bits_per_sample)
+    }
+
+    #[inline(always)]
+    fn frame_size(&self) -> usize {
+        (self.channels as usize) * (self.bits_per_sample as usize / 8)
+    }
+
+    fn check(&self) -> Result<(), WaveDecodeError> {
+        let format = self.format;
+        let bits = self.bits_per_sample;
+        match (format, bits) {
+            (WaveFormat::Pcm, 8 | 16 | 24 | 32 | 64) => Ok(()),
+            (WaveFormat::IeeeFloat, 32 | 64) => Ok(()),
+            (WaveFormat::Alaw | WaveFormat::Mulaw, 8) => Ok(()),
+            (WaveFormat::Pcm | WaveFormat::IeeeFloat | WaveFormat::Alaw | WaveFormat::Mulaw, _) => Err(WaveDecodeError::UnsupportedBitDepth { format, bits }),
+            _ => Err(WaveDecodeError::UnsupportedFormat(format)),
+        }
+    }
+
+    /* WAVE stores 8-bit PCM unsigned and wider PCM signed, other layouts have the sign bit flipped */
+    #[inline(always)]
+    fn flip_sign(&self) -> bool {
+        self.format == WaveFormat::Pcm && self.signed != (self.bits_per_sample > 8)
+    }
+}
+
+/**
+ * Reads headerless sample data into a `WaveFile`, e.g. to write it as WAVE afterwards.
+ */
+pub struct RawPcmReader<R>
+where R: Read {
+    source: R,
+    spec: RawPcmSpec,
+}
+
+impl<R> RawPcmReader<R>
+where R: Read {
+
+    pub fn new(source: R, spec: RawPcmSpec) -> RawPcmReader<R> {
+        RawPcmReader::<R> { source, spec }
+    }
+
+    /**
+     * Reads the whole source. An incomplete frame at the end is dropped and reported as warning.
+     */
+    pub fn decode(&mut self) -> Result<WaveFile, WaveDecodeError> {
+        self.spec.check()?;
+        if self.spec.channels == 0 {
+            return Err(WaveDecodeError::Unsupported(String::from("Raw sample data needs at least one channel")));
+        }
+        let frame_size = self.spec.frame_size();
+        let data_rate = match u16::try_from(frame_size).ok().and_then(|x| self.spec.sample_rate.checked_mul(x as u32)) {
+            Some(x) => x,
+            None => return Err(WaveDecodeError::Unsupported(format!(
+                "Frames of {} bytes at {} Hz exceed the limits of WAVE", frame_size, self.spec.sample_rate))),
+        };
+
+        let mut buf: Vec<u8> = Vec::new();
+        self.source.read_to_end(&mut buf)?;
+
+        let mut wave_file = WaveFile::new();
+        let len = buf.len() - buf.len() % frame_size;
+        if len != buf.len() {
+            wave_file.warnings.push(DecodeWarning::TrailingBytes { offset: len as u64, len: (buf.len() - len) as u64 });
+            buf.truncate(len);
+        }
+        convert(&mut buf, &self.spec, self.spec.endian);
+
+        wave_file.format = self.spec.format;
+        wave_file.channels = self.spec.channels;
+        wave_file.sample_rate = self.spec.sample_rate;
+        wave_file.bits_per_sample = self.spec.bits_per_sample;
+        wave_file.frame_size = frame_size as u16;
+        wave_file.data_rate = data_rate;
+        if wave_file.format != WaveFormat::Pcm {
+            wave_file.num_of_samples = Some( (len / frame_size) as u32 );
+        }
+        wave_file.sample_data = buf;
+
+        Ok(wave_file)
+    }
+}
+
+/**
+ * Writes the sample data of a `WaveFile` without any header. Only the byte layout is converted,
+ * format and bit depth of the file have to match the spec.
+ */
+pub struct RawPcmWriter<W>
+where W: Write {
+    sink: W,
+    spec: RawPcmSpec,
+}
+
+impl<W> RawPcmWriter<W>
+where W: Write {
+
+    pub fn new(sink: W, spec: RawPcmSpec) -> RawPcmWriter<W> {
+        RawPcmWriter::<W> { sink, spec }
+    }
+
+    pub fn into_inner(self) -> W {
+        self.sink
+    }
+
+    pub fn encode(&mut self, wave_file: &WaveFile) -> Result<(), IoError> {
+        if self.spec.check().is_err() || wave_file.format != self.spec.format || wave_file.bits_per_sample != self.spec.bits_per_sample {
+            return Err(IoError::new(ErrorKind::InvalidInput, format!(
+                "Cannot write {}-bit {} samples as {}-bit {}",
+                wave_file.bits_per_sample, wave_file.format.stringify(), self.spec.bits_per_sample, self.spec.format.stringify())));
+        }
+
+        let mut buf = wave_file.sample_data.clone();
+        convert(&mut buf, &self.spec, Endian::Little);
+        self.sink.write_all(&buf)?;
+        self.sink.flush()
+    }
+}
+
+/**
+ * Converts between the layout of `spec` and the little-endian layout of WAVE. `source` is the
+ * byte order of `buf` before the conversion, the sign bit is flipped in that order.
+ */
+fn convert(buf: &mut [u8], spec: &RawPcmSpec, source: Endian) {
+    let swap = spec.endian == Endian::Big;
+    let flip = spec.flip_sign();
+    if !swap && !flip {
+        return;
+    }
+
+    let bytes = spec.bits_per_sample as usize / 8;
+    let msb = match source {
+        Endian::Little => bytes - 1,
+        Endian::Big => 0,
+    };
+    for sample in buf.chunks_exact_mut(bytes) {
+        if flip {
+            sample[msb] ^= 0x80;
+        }
+        if swap {
+            sample.reverse();
+        }
+    }
+}
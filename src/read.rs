@@ -1,133 +1,346 @@
-use crate::{WaveFile, WaveFormat};
+use crate::{FourCC, WaveFile, WaveFormat};
 use crate::util::exts::{Endian, read::*};
-use crate::WaveDecodeError;
+use crate::util::text::TextEncoding;
+use crate::{DecodeWarning, WaveDecodeError};
+use crate::chunks::{Chunk, SharedSource, acid::AcidChunk, bext::BroadcastExtension, cue::read_cue_chunk, handler::{AnyChunkHandler, ChunkHandler}, cset::CharacterSet, inst::InstrumentChunk, info::{apply_info_list, read_info_list}};
 
 use std::io::{Cursor, ErrorKind};
+use std::ops::Range;
+use std::sync::Arc;
 use std::convert::From;
 
-const RIFF_MAGIC: u32 = 0x46464952;
-const WAVE_MAGIC: u32 = 0x45564157;
-const CHUNK_FMT_: u32 = 0x20746d66;
-const CHUNK_FACT: u32 = 0x74636166;
-const CHUNK_DATA: u32 = 0x61746164;
+pub(crate) const RIFF_MAGIC: u32 = 0x46464952;
+pub(crate) const WAVE_MAGIC: u32 = 0x45564157;
+pub(crate) const CHUNK_FMT_: u32 = 0x20746d66;
+pub(crate) const CHUNK_FACT: u32 = 0x74636166;
+pub(crate) const CHUNK_DATA: u32 = 0x61746164;
 const CHUNK_ID3_: u32 = 0x20336469;                 /* THIS IS NOT INCLUDED IN WAVE SPECIFICATION!! */
 const CHUNK_ID3_ALT: u32 = 0x20334449;
-const CHUNK_LIST: u32 = 0x5453494c;                 /* List chunk id */
-const CHUNK_LIST_SUB_INFO: u32 = 0x4f464e49;        /* List chunk type INFO */
-
-const LIST_INFO_IART: u32 = 0x54524149;	            /* The artist of the original subject of the file */
-const LIST_INFO_ICMT: u32 = 0x544d4349;	            /* General comments about the file or its subject */
-const LIST_INFO_ICOP: u32 = 0x504f4349;	            /* Copyright information about the file (e.g., "Copyright Some Company 2011") */
-const LIST_INFO_ICRD: u32 = 0x44524349;             /* The date the subject of the file was created (creation date) */
-const LIST_INFO_IGNR: u32 = 0x524e4749;	            /* The genre of the subject */
-const LIST_INFO_IKEY: u32 = 0x59454b49;	            /* A list of keywords for the file or its subject */
-const LIST_INFO_INAM: u32 = 0x4d414e49;	            /* Title of the subject of the file (name) */
-const LIST_INFO_IPRD: u32 = 0x44525049;	            /* Name of the title the subject was originally intended for */
-const LIST_INFO_ISBJ: u32 = 0x4a425349;	            /* Description of the contents of the file (subject) */
-const LIST_INFO_ISFT: u32 = 0x54465349;	            /* Name of the software package used to create the file */
+pub(crate) const CHUNK_LIST: u32 = 0x5453494c;      /* List chunk id */
+pub(crate) const CHUNK_LIST_SUB_INFO: u32 = 0x4f464e49; /* List chunk type INFO */
+pub(crate) const CHUNK_CSET: u32 = 0x54455343;      /* Character set of the text in the file */
+pub(crate) const CHUNK_INST: u32 = 0x74736e69;      /* Instrument chunk (sampler key mapping) */
+pub(crate) const CHUNK_ACID: u32 = 0x64696361;      /* ACID loop information */
+pub(crate) const CHUNK_IXML: u32 = 0x4c4d5869;      /* iXML production metadata */
+pub(crate) const CHUNK_AXML: u32 = 0x6c6d7861;      /* XML metadata of EBU Tech 3285 Supplement 5 */
+pub(crate) const CHUNK_BEXT: u32 = 0x74786562;      /* Broadcast audio extension (EBU Tech 3285) */
+pub(crate) const CHUNK_CUE_: u32 = 0x20657563;      /* Cue points */
+
+/**
+ * How strictly the reader adheres to the specification.
+ */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Strictness {
+    Strict,                 /* Any violation of the specification is an error */
+    Lenient,                /* Recover as much audio as possible, violations are reported as warnings */
+}
+
+/**
+ * Options that control how a WAVE file is decoded.
+ */
+#[derive(Clone, Copy, Debug)]
+pub struct DecodeOptions {
+    /* Encoding of text that is neither valid UTF-8 nor covered by a CSET chunk */
+    pub fallback_encoding: TextEncoding,
+    pub strictness: Strictness,
+}
+
+impl DecodeOptions {
+
+    pub fn new() -> DecodeOptions {
+        DecodeOptions {
+            fallback_encoding: TextEncoding::Windows1252,
+            strictness: Strictness::Strict,
+        }
+    }
+
+    /**
+     * Options for files that violate the specification, e.g. streamed files whose header
+     * was never finalized or files that were cut off.
+     */
+    pub fn lenient() -> DecodeOptions {
+        DecodeOptions { strictness: Strictness::Lenient, ..DecodeOptions::new() }
+    }
+}
+
+impl Default for DecodeOptions {
+
+    fn default() -> DecodeOptions {
+        DecodeOptions::new()
+    }
+}
+
+type WarningCallback = Box<dyn FnMut(&DecodeWarning)>;
 
 pub struct WaveReader<R> 
 where R: SizedDataRead {
     source: R,
+    decoder: Decoder,
+}
+
+/**
+ * Decodes a file that is completely held in memory, independent of the source it was read from.
+ */
+pub(crate) struct Decoder {
+    options: DecodeOptions,
+    handlers: Vec<Box<dyn AnyChunkHandler>>,
+    on_warning: Option<WarningCallback>,
+    streamed: bool,                 /* RIFF size was never written, sizes of 0 are placeholders */
+    copy_samples: bool,             /* False if the sample data stays in the source buffer, see `decode_in_place` */
+    data_range: Option<Range<usize>>,
+}
+
+/**
+ * Decodes a WAVE file that is completely held by `buf`. The buffer is not copied, it is kept
+ * for the payloads of the chunks.
+ */
+pub fn decode_buffer(buf: Vec<u8>, options: DecodeOptions) -> Result<WaveFile, WaveDecodeError> {
+    Decoder::new(options).decode_buffer(buf)
 }
 
 impl<R> WaveReader<R> 
 where R: SizedDataRead {
 
     pub fn new(source: R) -> WaveReader<R> {
-        WaveReader::<R> { source: source }
+        WaveReader::<R> { source: source, decoder: Decoder::new(DecodeOptions::new()) }
+    }
+
+    pub fn with_options(source: R, options: DecodeOptions) -> WaveReader<R> {
+        WaveReader::<R> { source, decoder: Decoder::new(options) }
+    }
+
+    /**
+     * Registers a parser for chunks that are not interpreted by this library. If multiple
+     * handlers are registered for the same id, the first one is used.
+     */
+    pub fn register_handler<H: ChunkHandler + 'static>(&mut self, handler: H) {
+        self.decoder.handlers.push(Box::new(handler));
+    }
+
+    /**
+     * Registers a callback that is invoked for every warning as soon as it is found. Warnings
+     * are collected in `WaveFile::warnings` regardless of this callback.
+     */
+    pub fn on_warning<F: FnMut(&DecodeWarning) + 'static>(&mut self, callback: F) {
+        self.decoder.on_warning = Some(Box::new(callback));
     }
 
     pub fn decode(&mut self) -> Result<WaveFile, WaveDecodeError> {
         let mut buf: Vec<u8> = Vec::new();
-        let bytes_read = self.source.read_to_end(&mut buf)?;
+        self.source.read_to_end(&mut buf)?;
+        self.decoder.decode_buffer(buf)
+    }
+}
 
-        let mut cursor: Cursor<Vec<u8>> = Cursor::new(buf);
+impl Decoder {
+
+    pub(crate) fn new(options: DecodeOptions) -> Decoder {
+        Decoder { options, handlers: Vec::new(), on_warning: None, streamed: false, copy_samples: true, data_range: None }
+    }
+
+    /**
+     * Decodes a file that is completely held by `buf`, the buffer is kept for the chunk payloads.
+     */
+    pub(crate) fn decode_buffer(&mut self, mut buf: Vec<u8>) -> Result<WaveFile, WaveDecodeError> {
+        self.data_range = None;
+        let mut wave_file = self.decode_bytes(&buf)?;
+
+        // The sample data was copied, the buffer is only kept for the payloads of the other chunks.
+        let removed = self.data_range.take().unwrap_or(0..0);
+        buf.drain(removed.clone());
+        buf.shrink_to_fit();
+        let source = SharedSource::new(Arc::new(buf));
+        for chunk in wave_file.chunks.iter_mut() {
+            chunk.share(&source, &removed);
+        }
+
+        Ok(wave_file)
+    }
+
+    /**
+     * Decodes a file that is completely held by `buf` without copying the sample data. The sample
+     * data of the returned file is empty, it is found at the returned range of `buf` instead.
+     * Payloads of the other chunks are borrowed from `buf` as well.
+     */
+    #[cfg(feature = "mmap")]
+    pub(crate) fn decode_in_place<B>(&mut self, buf: Arc<B>) -> Result<(WaveFile, Range<usize>), WaveDecodeError>
+    where B: AsRef<[u8]> + Send + Sync + 'static {
+        self.copy_samples = false;
+        self.data_range = None;
+        let mut wave_file = self.decode_bytes(buf.as_ref().as_ref())?;
+
+        let source = SharedSource::new(buf);
+        for chunk in wave_file.chunks.iter_mut() {
+            chunk.share(&source, &(0..0));
+        }
+
+        Ok((wave_file, self.data_range.take().unwrap_or(0..0)))
+    }
+
+    fn decode_bytes(&mut self, buf: &[u8]) -> Result<WaveFile, WaveDecodeError> {
+        let bytes_read = buf.len();
+        let mut cursor: Cursor<&[u8]> = Cursor::new(buf);
 
         /* Read leading file information to verify it's actually a riff wave file. */
+        if bytes_read < 12 {
+            return Err(WaveDecodeError::InvalidHeader);
+        }
         let file_header = cursor.read_u32(Endian::Little)?;
-        let file_size = cursor.read_u32(Endian::Little)? + 8;    /* Adding 8 because the information does not contain file_header and file_size. */
+        let riff_size = cursor.read_u32(Endian::Little)?;
         let file_format = cursor.read_u32(Endian::Little)?;
         if file_header != RIFF_MAGIC || file_format != WAVE_MAGIC {
-            return Err(WaveDecodeError { message: "Source has invalid RIFF WAVE header".to_string() });
+            return Err(WaveDecodeError::InvalidHeader);
         }
 
-        if (file_size as usize) != bytes_read {
+        let mut wave_file = WaveFile::new();
+        let mut file_size = (riff_size as u64) + 8;    /* Adding 8 because the information does not contain file_header and file_size. */
+        if file_size != (bytes_read as u64) {
             /* Buffer does not contain same amount of data that is specified in header */
-            /* TODO: Abort or just proceed with actual file size? */
-            return Err(WaveDecodeError { message: format!("Bytestream size ({} B) is not equal to specified file size in riff header ({} B)", file_size, bytes_read) });
+            let declared = file_size;
+            let actual = bytes_read as u64;
+            match self.options.strictness {
+                Strictness::Strict => return Err(WaveDecodeError::SizeMismatch { declared, actual }),
+                Strictness::Lenient => {
+                    self.warn(&mut wave_file, DecodeWarning::SizeMismatch { declared, actual });
+                    file_size = actual;
+                },
+            };
         }
+        self.streamed = riff_size == 0 || riff_size == u32::MAX;
 
-        let mut wave_file = WaveFile::new();
-        wave_file.file_size = file_size;
+        wave_file.file_size = file_size.min(u32::MAX as u64) as u32;
         self.read_chunks(&mut wave_file, &mut cursor)?;
 
         Ok(wave_file)
     }
 
-    fn read_chunks(&mut self, wave_file: &mut WaveFile, cursor: &mut Cursor<Vec<u8>>) -> Result<(), WaveDecodeError> {
-        let mut has_fmt: bool = false;
-        let mut has_fact: bool = false;
+    fn read_chunks(&mut self, wave_file: &mut WaveFile, cursor: &mut Cursor<&[u8]>) -> Result<(), WaveDecodeError> {
+        let mut info_fields: Vec<(FourCC, Vec<u8>)> = Vec::new();
 
         loop {
-            if cursor.position() == (wave_file.file_size as u64) {
+            if cursor.position() >= (wave_file.file_size as u64) {
                 break;
             }
             
-            match cursor.read_u32(Endian::Little) {
-                Ok(CHUNK_FMT_) => {
-                    self.read_fmt_chunk(wave_file, cursor)?;
-                    has_fmt = true;
-                },
-                Ok(CHUNK_FACT) => {
-                    self.read_fact_chunk(wave_file, cursor)?;
-                    has_fact = true;
-                },
-                Ok(CHUNK_DATA) => self.read_data_chunk(wave_file, cursor)?,
-                Ok(CHUNK_LIST) => self.read_list_chunk(wave_file, cursor)? ,
-                Ok(CHUNK_ID3_) | Ok(CHUNK_ID3_ALT) => self.read_id3_chunk(wave_file, cursor)? ,
-                Ok(x) => { 
-                    println!("Skipping unexpected chunk {:x} at {}", x, cursor.position() - 4);
-                    let chunk_size = cursor.read_u32(Endian::Little)?;
-                    cursor.skip_bytes(chunk_size)?;
-                },
+            let offset = cursor.position();
+            let id = match cursor.read_u32(Endian::Little) {
+                Ok(x) => x,
                 Err(e) => {
                     match e.kind() {
                         ErrorKind::UnexpectedEof => {
                             /* Indicates either garbage bytes at the end or another problem */
-                            println!("Unexpected EOF at {} with file size {}", cursor.position(), wave_file.file_size); 
+                            let len = (cursor.get_ref().len() as u64).saturating_sub(offset);
+                            self.warn(wave_file, DecodeWarning::TrailingBytes { offset, len });
                             break; 
                         },
                         _ => return Err(WaveDecodeError::from(e)),
                     };
                 },
             };
+
+            match self.read_chunk(id, wave_file, &mut info_fields, cursor).map_err(|e| e.at(offset)) {
+                Ok(()) => (),
+                Err(WaveDecodeError::Truncated { .. }) if self.options.strictness == Strictness::Lenient => {
+                    self.warn(wave_file, DecodeWarning::TruncatedChunk { id: FourCC::from_u32(id), offset });
+                    break;
+                },
+                Err(e) => return Err(e),
+            };
+
+            // Every chunk is retained with its raw payload, so it can be inspected or written again.
+            let chunk = Chunk::from_source(cursor.get_ref(), id, offset, id != CHUNK_DATA);
+            let truncated = id != CHUNK_DATA && offset + 8 + (chunk.size as u64) > (cursor.get_ref().len() as u64);
+            wave_file.chunks.push(chunk);
+            if truncated && self.options.strictness == Strictness::Lenient {
+                self.warn(wave_file, DecodeWarning::TruncatedChunk { id: FourCC::from_u32(id), offset });
+                break;
+            }
         }
 
-        if !has_fmt {
-            return Err(WaveDecodeError { message: "WAVE file does not have mandatory format chunk".to_string() });
+        let charset = wave_file.charset.and_then(|x| x.encoding());
+        apply_info_list(&mut wave_file.metadata, &info_fields, charset, self.options.fallback_encoding);
+
+        if wave_file.chunk(FourCC::from_u32(CHUNK_FMT_)).is_none() {
+            return Err(WaveDecodeError::MissingChunk(FourCC::from_u32(CHUNK_FMT_)));
         }
-        if wave_file.format != WaveFormat::Pcm && !has_fact {
+        if wave_file.format != WaveFormat::Pcm && wave_file.chunk(FourCC::from_u32(CHUNK_FACT)).is_none() {
             /* If the format is not PCM the file needs to have a fact chunk (see specification Rev. 3). */
-            return Err(WaveDecodeError { message: "Mandatory FACT chunk could not be found in this file".to_string() });
+            match self.options.strictness {
+                Strictness::Strict => return Err(WaveDecodeError::MissingChunk(FourCC::from_u32(CHUNK_FACT))),
+                Strictness::Lenient => self.warn(wave_file, DecodeWarning::MissingChunk(FourCC::from_u32(CHUNK_FACT))),
+            };
         }
 
         Ok(())
     }
 
+    /**
+     * Reads a single chunk, the cursor is positioned right behind its id.
+     */
+    fn read_chunk(&mut self, id: u32, wave_file: &mut WaveFile, info_fields: &mut Vec<(FourCC, Vec<u8>)>, cursor: &mut Cursor<&[u8]>) -> Result<(), WaveDecodeError> {
+        match id {
+            CHUNK_FMT_ => self.read_fmt_chunk(wave_file, cursor)?,
+            CHUNK_FACT => self.read_fact_chunk(wave_file, cursor)?,
+            CHUNK_DATA => self.read_data_chunk(wave_file, cursor)?,
+            CHUNK_LIST => self.read_list_chunk(wave_file, info_fields, cursor)? ,
+            CHUNK_CSET => self.read_cset_chunk(wave_file, cursor)?,
+            CHUNK_ID3_ | CHUNK_ID3_ALT => self.read_id3_chunk(wave_file, cursor)? ,
+            CHUNK_INST => self.read_inst_chunk(wave_file, cursor)?,
+            CHUNK_ACID => self.read_acid_chunk(wave_file, cursor)?,
+            CHUNK_IXML => wave_file.ixml = Some( self.read_xml_chunk(cursor)? ),
+            CHUNK_AXML => wave_file.axml = Some( self.read_xml_chunk(cursor)? ),
+            CHUNK_BEXT => self.read_bext_chunk(wave_file, cursor)?,
+            CHUNK_CUE_ => self.read_cue_chunk(wave_file, cursor)?,
+            x => match self.handlers.iter().position(|h| h.id().as_u32() == x) {
+                Some(handler) => {
+                    let chunk_size = cursor.read_u32(Endian::Little)?;
+                    let start = cursor.position() as usize;
+                    let end = start + (chunk_size as usize);
+                    let data = match cursor.get_ref().get(start..end) {
+                        Some(data) => data,
+                        None => return Err(WaveDecodeError::Truncated { offset: 0 }),
+                    };
+                    wave_file.custom_chunks.push((FourCC::from_u32(x), Arc::from(self.handlers[handler].parse(data)?)));
+                    cursor.skip_bytes(chunk_size + chunk_size % 2)?;
+                },
+                None => {
+                    self.warn(wave_file, DecodeWarning::UnknownChunk { id: FourCC::from_u32(x), offset: cursor.position() - 4 });
+                    let chunk_size = cursor.read_u32(Endian::Little)?;
+                    cursor.skip_bytes(chunk_size + chunk_size % 2)?;
+                },
+            },
+        };
+
+        Ok(())
+    }
+
+    /**
+     * Records a warning and forwards it to the callback and the `log` crate.
+     */
+    fn warn(&mut self, wave_file: &mut WaveFile, warning: DecodeWarning) {
+        #[cfg(feature = "log")]
+        log::warn!("{}", warning);
+
+        if let Some(callback) = self.on_warning.as_mut() {
+            callback(&warning);
+        }
+        wave_file.warnings.push(warning);
+    }
+
     /**
      * 
      */
-    fn read_fmt_chunk(&self, wave_file: &mut WaveFile, cursor: &mut Cursor<Vec<u8>>) -> Result<(), WaveDecodeError> {
+    fn read_fmt_chunk(&self, wave_file: &mut WaveFile, cursor: &mut Cursor<&[u8]>) -> Result<(), WaveDecodeError> {
         let sect_length = cursor.read_u32(Endian::Little)?;
         match sect_length {
             16 | 18 | 40 => true,
-            _ => return Err(WaveDecodeError { message: format!("Unexpected fmt section length: {}", sect_length) }),
+            _ => return Err(WaveDecodeError::invalid_chunk(CHUNK_FMT_, format!("Unexpected fmt section length: {}", sect_length))),
         };
 
-        wave_file.format = match WaveFormat::parse( cursor.read_u16(Endian::Little)? ) {
+        let format_tag = cursor.read_u16(Endian::Little)?;
+        wave_file.format = match WaveFormat::parse(format_tag) {
             Some(x) => x,
-            None => return Err(WaveDecodeError { message: String::from("Unknown wave type.") }),
+            None => return Err(WaveDecodeError::UnknownFormat(format_tag)),
         };
         wave_file.channels = cursor.read_u16(Endian::Little)?;
         wave_file.sample_rate = cursor.read_u32(Endian::Little)?;
@@ -146,7 +359,7 @@ where R: SizedDataRead {
                 0 => (),
                 _ => { // Extension size must be present when dealing with non-PCM format
                     if wave_file.format != WaveFormat::Pcm {
-                        return Err(WaveDecodeError { message: String::from("Invalid or no extension size field.") });
+                        return Err(WaveDecodeError::invalid_chunk(CHUNK_FMT_, String::from("Invalid or no extension size field")));
                     }
                 }
             };
@@ -158,7 +371,7 @@ where R: SizedDataRead {
     /** 
      * 
      */
-    fn read_fact_chunk(&self, wave_file: &mut WaveFile, cursor: &mut Cursor<Vec<u8>>) -> Result<(), WaveDecodeError> {
+    fn read_fact_chunk(&self, wave_file: &mut WaveFile, cursor: &mut Cursor<&[u8]>) -> Result<(), WaveDecodeError> {
         // Fact chunk length, can be abandoned here cause fact chunk usually has only one field.
         cursor.read_u32(Endian::Little)?; 
 
@@ -170,57 +383,31 @@ where R: SizedDataRead {
     }
 
     /**
-     * 
+     * Reads a LIST chunk, only lists of type INFO are supported. In lenient mode other lists
+     * are kept as raw chunks.
      */
-    fn read_list_chunk(&self, wave_file: &mut WaveFile, cursor: &mut Cursor<Vec<u8>>) -> Result<(), WaveDecodeError> {
+    fn read_list_chunk(&mut self, wave_file: &mut WaveFile, info_fields: &mut Vec<(FourCC, Vec<u8>)>, cursor: &mut Cursor<&[u8]>) -> Result<(), WaveDecodeError> {
         let chunk_size = cursor.read_u32(Endian::Little)?;
-        if cursor.read_u32(Endian::Little)? != CHUNK_LIST_SUB_INFO {
-            return Err(WaveDecodeError { message: String::from("Unsupported subchunk in LIST chunk") });
-        }
-
-        let mut count: u32 = 4;     // After reading chunk_size another 4 bytes have been read.
-        while count < chunk_size  {
-            let info_size;
-            match cursor.read_u32(Endian::Little) {
-                Ok(x) => {
-                    info_size = cursor.read_u32(Endian::Little)?;
-                    match x {
-                        LIST_INFO_IART => wave_file.metadata.artist = Some( cursor.read_string(info_size)? ),
-                        LIST_INFO_ICOP => wave_file.metadata.copyright = Some( cursor.read_string(info_size)? ),
-                        LIST_INFO_ICRD => wave_file.metadata.date = Some( cursor.read_string(info_size)? ),
-                        LIST_INFO_IGNR => wave_file.metadata.genre = Some( cursor.read_string(info_size)? ),
-                        LIST_INFO_IKEY => wave_file.metadata.keywords = Some( cursor.read_string(info_size)? ),
-                        LIST_INFO_INAM => wave_file.metadata.name = Some( cursor.read_string(info_size)? ),
-                        LIST_INFO_IPRD => wave_file.metadata.title = Some( cursor.read_string(info_size)? ),
-                        LIST_INFO_ISFT => wave_file.metadata.encoder = Some( cursor.read_string(info_size)? ),
-                        LIST_INFO_ICMT => wave_file.metadata.comments = Some( cursor.read_string(info_size)? ),
-                        LIST_INFO_ISBJ => wave_file.metadata.description = Some( cursor.read_string(info_size)? ),
-                        // All other info types are currently not supported, therefore skip.
-                        _ => cursor.skip_bytes(info_size)?,
-                    };
-                },
-                Err(e) => {
-                    match e.kind() {
-                        ErrorKind::UnexpectedEof => break,
-                        _ => return Err(WaveDecodeError { message: format!("Read error: {}", e) }),
-                    }
-                },
-            }
-
-            // Text information needs to be word-aligned (2-byte aligned)
-            let dist = cursor.position() % 2;
-            if dist != 0 {
-                cursor.skip_bytes(dist as u32)?;
-            }
-            // Increase counter by info id size, size of 'size' field, the actual size of the information and potential padding.
-            count += 8 + info_size + (dist as u32);
-        }
+        let start = cursor.position();
+        let list_type = match chunk_size {
+            0..=3 => 0,
+            _ => cursor.read_u32(Endian::Little)?,
+        };
+        match (list_type, self.options.strictness) {
+            (CHUNK_LIST_SUB_INFO, _) => info_fields.extend(read_info_list(cursor, chunk_size - 4)?),
+            (_, Strictness::Lenient) => {
+                let list_type = FourCC::from_u32(list_type);
+                self.warn(wave_file, DecodeWarning::UnknownListType { list_type, offset: start - 8 });
+            },
+            (_, Strictness::Strict) => return Err(WaveDecodeError::invalid_chunk(CHUNK_LIST, String::from("Unsupported subchunk in LIST chunk"))),
+        };
 
+        cursor.set_position(start + (chunk_size as u64) + (chunk_size % 2) as u64);
         Ok(())
     }
 
     // As ID3 is used in multiple audio formats the actual parsing should be placed in a separate module for reuse in other format decoders
-    fn read_id3_chunk(&self, _wave_file: &mut WaveFile, cursor: &mut Cursor<Vec<u8>>) -> Result<(), WaveDecodeError> {
+    fn read_id3_chunk(&self, _wave_file: &mut WaveFile, cursor: &mut Cursor<&[u8]>) -> Result<(), WaveDecodeError> {
         // Do something with chunk info
         // TODO: pass over to separate ID3 parser
         let size = cursor.read_u32(Endian::Little)?;
@@ -229,14 +416,104 @@ where R: SizedDataRead {
         Ok(())
     }
 
+    /**
+     * Reads the instrument chunk holding base note, detune, gain and key/velocity ranges.
+     */
+    fn read_inst_chunk(&self, wave_file: &mut WaveFile, cursor: &mut Cursor<&[u8]>) -> Result<(), WaveDecodeError> {
+        let size = cursor.read_u32(Endian::Little)?;
+        let start = cursor.position();
+        wave_file.instrument = Some( InstrumentChunk::read(cursor, size)? );
+
+        // The payload is usually 7 bytes long, so the chunk is followed by a pad byte.
+        cursor.set_position(start + (size as u64) + (size % 2) as u64);
+        Ok(())
+    }
+
+    /**
+     * Reads the ACID chunk holding tempo, number of beats, meter and root note of a loop.
+     */
+    fn read_acid_chunk(&self, wave_file: &mut WaveFile, cursor: &mut Cursor<&[u8]>) -> Result<(), WaveDecodeError> {
+        let size = cursor.read_u32(Endian::Little)?;
+        let start = cursor.position();
+        wave_file.acid = Some( AcidChunk::read(cursor, size)? );
+
+        cursor.set_position(start + (size as u64) + (size % 2) as u64);
+        Ok(())
+    }
+
+    /**
+     * Reads the CSET chunk, which declares the codepage of the text in INFO fields.
+     */
+    fn read_cset_chunk(&self, wave_file: &mut WaveFile, cursor: &mut Cursor<&[u8]>) -> Result<(), WaveDecodeError> {
+        let size = cursor.read_u32(Endian::Little)?;
+        let start = cursor.position();
+        wave_file.charset = Some( CharacterSet::read(cursor, size)? );
+
+        cursor.set_position(start + (size as u64) + (size % 2) as u64);
+        Ok(())
+    }
+
+    /**
+     * Reads the broadcast audio extension chunk.
+     */
+    fn read_bext_chunk(&self, wave_file: &mut WaveFile, cursor: &mut Cursor<&[u8]>) -> Result<(), WaveDecodeError> {
+        let size = cursor.read_u32(Endian::Little)?;
+        let start = cursor.position();
+        wave_file.bext = Some( BroadcastExtension::read(cursor, size, self.options.fallback_encoding)? );
+
+        cursor.set_position(start + (size as u64) + (size % 2) as u64);
+        Ok(())
+    }
+
+    /**
+     * Reads the cue points of the cue chunk.
+     */
+    fn read_cue_chunk(&self, wave_file: &mut WaveFile, cursor: &mut Cursor<&[u8]>) -> Result<(), WaveDecodeError> {
+        let size = cursor.read_u32(Endian::Little)?;
+        let start = cursor.position();
+        wave_file.cue_points = read_cue_chunk(cursor, size)?;
+
+        cursor.set_position(start + (size as u64) + (size % 2) as u64);
+        Ok(())
+    }
+
+    /**
+     * Reads an XML chunk (iXML or aXML) as string. Recorders often preallocate these chunks
+     * and fill the remaining space with NUL characters, which are stripped here.
+     */
+    fn read_xml_chunk(&self, cursor: &mut Cursor<&[u8]>) -> Result<String, WaveDecodeError> {
+        let size = cursor.read_u32(Endian::Little)?;
+        let start = cursor.position();
+        let xml = cursor.read_string(size, self.options.fallback_encoding)?;
+
+        cursor.set_position(start + (size as u64) + (size % 2) as u64);
+        Ok(xml)
+    }
+
     /** 
      * 
      */
-    fn read_data_chunk(&self, wave_file: &mut WaveFile, cursor: &mut Cursor<Vec<u8>>) -> Result<(), WaveDecodeError> {
+    fn read_data_chunk(&mut self, wave_file: &mut WaveFile, cursor: &mut Cursor<&[u8]>) -> Result<(), WaveDecodeError> {
         let size = cursor.read_u32(Endian::Little)?;
 
         let curr_pos = cursor.position() as usize;
-        wave_file.sample_data = (cursor.get_ref()[curr_pos..(curr_pos + (size as usize))]).to_vec();
+        let available = cursor.get_ref().len() - curr_pos;
+        if self.options.strictness == Strictness::Lenient && ((size as usize) > available || (size == 0 && self.streamed)) {
+            // The stated size is a placeholder or the file was cut off, all remaining whole frames are used instead.
+            let frame_size = (wave_file.frame_size as usize).max(1);
+            let len = available - available % frame_size;
+            self.set_sample_data(wave_file, cursor.get_ref(), curr_pos..(curr_pos + len));
+            self.warn(wave_file, DecodeWarning::DataClamped { offset: (curr_pos as u64) - 8, declared: size, actual: len as u32 });
+
+            cursor.set_position(cursor.get_ref().len() as u64);
+            return Ok(());
+        }
+
+        let range = curr_pos..(curr_pos + (size as usize));
+        if range.end > cursor.get_ref().len() {
+            return Err(WaveDecodeError::Truncated { offset: 0 });
+        }
+        self.set_sample_data(wave_file, cursor.get_ref(), range);
         cursor.skip_bytes(size)?;
 
         // Size of data chunk can be odd, then a pad byte is at the current position of the cursor. Need to skip this.
@@ -247,4 +524,16 @@ where R: SizedDataRead {
 
         Ok(())
     }
-}
\ No newline at end of file
+
+    fn set_sample_data(&mut self, wave_file: &mut WaveFile, buf: &[u8], range: Range<usize>) {
+        if self.copy_samples {
+            wave_file.sample_data = buf[range.clone()].to_vec();
+        }
+        self.data_range = Some(range);
+    }
+}
+
+/* Chunk ids consist of printable ASCII characters */
+pub(crate) fn is_plausible_id(bytes: &[u8]) -> bool {
+    bytes.iter().all(|x| (0x20..=0x7e).contains(x))
+}
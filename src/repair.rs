@@ -0,0 +1,139 @@
+use crate::{DecodeWarning, FourCC, WaveDecodeError};
+use crate::edit::SetLen;
+use crate::read::*;
+use crate::util::exts::{Endian, read::*, write::*};
+use crate::write::WaveWriter;
+
+use std::io::{Read, Seek, SeekFrom, Write};
+
+/**
+ * Header sizes before and after a repair.
+ */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RepairReport {
+    pub old_riff_size: u32,
+    pub new_riff_size: u32,
+    pub old_data_size: u32,
+    pub new_data_size: u32,
+    pub removed_bytes: u64,         /* Incomplete frames and chunks cut off at the end of the file */
+}
+
+impl RepairReport {
+
+    pub fn changed(&self) -> bool {
+        self.old_riff_size != self.new_riff_size || self.old_data_size != self.new_data_size || self.removed_bytes != 0
+    }
+}
+
+/**
+ * Repairs the header of a WAVE file in place, e.g. of a recording that was never finalized.
+ * The chunk structure is rescanned and the RIFF and data sizes are recomputed from the file
+ * length. If the data chunk exceeds the file, it is cut to whole frames, incomplete chunks
+ * and garbage at the end of the file are removed.
+ */
+pub fn repair<F>(file: &mut F) -> Result<RepairReport, WaveDecodeError>
+where F: Read + Write + Seek + SetLen {
+    let file_len = file.seek(SeekFrom::End(0))?;
+    file.seek(SeekFrom::Start(0))?;
+    if file_len < 12 {
+        return Err(WaveDecodeError::InvalidHeader);
+    }
+    let file_header = file.read_u32(Endian::Little)?;
+    let old_riff_size = file.read_u32(Endian::Little)?;
+    let file_format = file.read_u32(Endian::Little)?;
+    if file_header != RIFF_MAGIC || file_format != WAVE_MAGIC {
+        return Err(WaveDecodeError::InvalidHeader);
+    }
+
+    let mut frame_size: Option<u16> = None;
+    let mut data: Option<(u64, u32, u32)> = None;     /* Offset, old and new size of the data chunk */
+    let mut end: u64 = 12;
+    while end + 8 <= file_len {
+        file.seek(SeekFrom::Start(end))?;
+        let id = file.read_u32(Endian::Little)?;
+        let size = file.read_u32(Endian::Little)?;
+        if !is_plausible_id(&id.to_le_bytes()) {
+            break;
+        }
+
+        let chunk_end = end + 8 + (size as u64);
+        match id {
+            CHUNK_FMT_ => {
+                file.seek(SeekFrom::Start(end + 20))?;
+                frame_size = Some( file.read_u16(Endian::Little)? );
+            },
+            CHUNK_DATA => {
+                let block = match frame_size {
+                    Some(x) if x != 0 => x as u64,
+                    Some(_) => 1,
+                    None => return Err(WaveDecodeError::MissingChunk(FourCC::from_u32(CHUNK_FMT_))),
+                };
+                // Sizes exceeding the file are placeholders of a recording that was never finalized. So is a size
+                // of 0, unless further chunks follow, then the data is actually empty.
+                let followed = chunk_end + (size % 2) as u64 + 8 <= file_len && next_id_plausible(file, chunk_end + (size % 2) as u64)?;
+                if (size == 0 && !followed) || chunk_end > file_len || (chunk_end + (size % 2) as u64 != file_len && !followed) {
+                    let available = (file_len - end - 8).min(u32::MAX as u64);
+                    let new_size = (available - available % block) as u32;
+                    data = Some((end, size, new_size));
+                    end += 8 + (new_size as u64);
+                    break;
+                }
+                data = Some((end, size, size));
+            },
+            _ if chunk_end > file_len => break,
+            _ => (),
+        };
+        end = chunk_end + (size % 2) as u64;
+    }
+    end = end.min(file_len);
+
+    let (data_offset, old_data_size, new_data_size) = match data {
+        Some(x) => x,
+        None => return Err(WaveDecodeError::MissingChunk(FourCC::from_u32(CHUNK_DATA))),
+    };
+
+    let mut new_len = end;
+    if new_data_size % 2 == 1 && data_offset + 8 + (new_data_size as u64) == end {
+        new_len += 1;
+    }
+    let new_riff_size = match new_len - 8 > (u32::MAX as u64) {
+        true => return Err(WaveDecodeError::Unsupported(String::from("WAVE file exceeds 4 GiB"))),
+        false => (new_len - 8) as u32,
+    };
+
+    file.set_len(new_len)?;
+    if new_len > end {
+        file.seek(SeekFrom::Start(end))?;
+        file.write_u8(0)?;
+    }
+    file.seek(SeekFrom::Start(4))?;
+    file.write_u32(new_riff_size, Endian::Little)?;
+    file.seek(SeekFrom::Start(data_offset + 4))?;
+    file.write_u32(new_data_size, Endian::Little)?;
+    file.flush()?;
+
+    Ok(RepairReport {
+        old_riff_size,
+        new_riff_size,
+        old_data_size,
+        new_data_size,
+        removed_bytes: file_len.saturating_sub(end),
+    })
+}
+
+/**
+ * Decodes `source` leniently and writes a well-formed copy to `sink`. Returns the issues
+ * that were found while decoding.
+ */
+pub fn repair_copy<R, W>(source: R, sink: W) -> Result<Vec<DecodeWarning>, WaveDecodeError>
+where R: SizedDataRead, W: Write {
+    let wave_file = WaveReader::with_options(source, DecodeOptions::lenient()).decode()?;
+    WaveWriter::new(sink).encode(&wave_file)?;
+
+    Ok(wave_file.warnings)
+}
+
+fn next_id_plausible<F: Read + Seek>(file: &mut F, pos: u64) -> Result<bool, WaveDecodeError> {
+    file.seek(SeekFrom::Start(pos))?;
+    Ok(is_plausible_id(&file.read_u32(Endian::Little)?.to_le_bytes()))
+}
@@ -1,7 +1,16 @@
 use std::fs::File;
+use std::io::Cursor;
 use std::path::PathBuf;
 
 use crate::read::WaveReader;
+use crate::{FourCC, WaveDecodeError};
+use crate::chunks::handler::ChunkHandler;
+use crate::read::DecodeOptions;
+use crate::write::WaveWriter;
+use crate::edit::WaveEditor;
+use crate::chunks::cue::CuePoint;
+use crate::util::text::TextEncoding;
+use crate::chunks::{acid::AcidChunk, inst::InstrumentChunk, info::write_info_list};
 use rodio;
 
 #[test]
@@ -22,7 +31,7 @@ fn read_wav_file() -> () {
     let seconds = wave_file.duration().as_secs() % 60;
     println!("Duration: {:02}:{:02}", minutes, seconds);
 
-    match wave_file.metadata.name {
+    match wave_file.metadata.title {
         Some(x) => match wave_file.metadata.artist {
             Some(y) => println!("Playing '{}' by '{}'", x, y),
             None => println!("Playing '{}'", x),
@@ -40,4 +49,936 @@ fn read_wav_file() -> () {
     sink.append(sample_buffer);
 
     sink.sleep_until_end();
-}
\ No newline at end of file
+}
+/**
+ * Builds a chunk with header and pad byte from its id and payload.
+ */
+fn build_chunk(id: &[u8; 4], payload: &[u8]) -> Vec<u8> {
+    let mut chunk = id.to_vec();
+    chunk.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+    chunk.extend_from_slice(payload);
+    if payload.len() & 1 == 1 {
+        chunk.push(0);
+    }
+    chunk
+}
+
+/**
+ * Builds a RIFF WAVE bytestream with a 16-bit stereo PCM format chunk followed by `chunks`.
+ */
+fn build_wave(chunks: &[Vec<u8>]) -> Vec<u8> {
+    let mut fmt: Vec<u8> = Vec::new();
+    fmt.extend_from_slice(&1_u16.to_le_bytes());
+    fmt.extend_from_slice(&2_u16.to_le_bytes());
+    fmt.extend_from_slice(&44100_u32.to_le_bytes());
+    fmt.extend_from_slice(&(44100_u32 * 4).to_le_bytes());
+    fmt.extend_from_slice(&4_u16.to_le_bytes());
+    fmt.extend_from_slice(&16_u16.to_le_bytes());
+
+    let mut body = b"WAVE".to_vec();
+    body.extend(build_chunk(b"fmt ", &fmt));
+    for chunk in chunks {
+        body.extend_from_slice(chunk);
+    }
+
+    let mut wave = b"RIFF".to_vec();
+    wave.extend_from_slice(&(body.len() as u32).to_le_bytes());
+    wave.extend(body);
+    wave
+}
+
+#[test]
+fn inst_and_acid_round_trip() {
+    let inst = InstrumentChunk { unshifted_note: 57, fine_tune: -12, gain: -3, low_note: 48, high_note: 72, low_velocity: 1, high_velocity: 127 };
+    let mut acid = AcidChunk::new();
+    acid.tempo = 128.0;
+    acid.num_beats = 8;
+    acid.set_root_note(Some(57));
+
+    let mut inst_bytes: Vec<u8> = Vec::new();
+    inst.write(&mut inst_bytes).unwrap();
+    let mut acid_bytes: Vec<u8> = Vec::new();
+    acid.write(&mut acid_bytes).unwrap();
+
+    let data = build_chunk(b"data", &[0; 16]);
+    let wave = build_wave(&[inst_bytes.clone(), acid_bytes.clone(), data]);
+    let wave_file = WaveReader::new(Cursor::new(wave)).decode().unwrap();
+
+    assert_eq!(wave_file.instrument, Some(inst));
+    assert_eq!(wave_file.acid, Some(acid));
+    assert_eq!(wave_file.acid.unwrap().root_note(), Some(57));
+    assert!(!wave_file.acid.unwrap().is_one_shot());
+
+    let mut rewritten: Vec<u8> = Vec::new();
+    wave_file.instrument.unwrap().write(&mut rewritten).unwrap();
+    wave_file.acid.unwrap().write(&mut rewritten).unwrap();
+    assert_eq!(rewritten, [inst_bytes, acid_bytes].concat());
+}
+
+#[test]
+fn ixml_fields() {
+    let xml = "<?xml version=\"1.0\" encoding=\"UTF-8\"?><BWFXML><IXML_VERSION>2.10</IXML_VERSION>\
+        <PROJECT>Night &amp; Day</PROJECT><SCENE>12A</SCENE><TAKE>3</TAKE><TAPE>230614</TAPE><CIRCLED>TRUE</CIRCLED>\
+        <NOTE>wind</NOTE><SPEED><NOTE>speed note</NOTE><MASTER_SPEED>25/1</MASTER_SPEED><TIMECODE_RATE>25/1</TIMECODE_RATE>\
+        <TIMECODE_FLAG>NDF</TIMECODE_FLAG><FILE_SAMPLE_RATE>48000</FILE_SAMPLE_RATE><AUDIO_BIT_DEPTH>24</AUDIO_BIT_DEPTH>\
+        <TIMESTAMP_SAMPLES_SINCE_MIDNIGHT_HI>0</TIMESTAMP_SAMPLES_SINCE_MIDNIGHT_HI>\
+        <TIMESTAMP_SAMPLES_SINCE_MIDNIGHT_LO>1728000000</TIMESTAMP_SAMPLES_SINCE_MIDNIGHT_LO>\
+        <TIMESTAMP_SAMPLE_RATE>48000</TIMESTAMP_SAMPLE_RATE></SPEED>\
+        <TRACK_LIST><TRACK_COUNT>2</TRACK_COUNT>\
+        <TRACK><CHANNEL_INDEX>1</CHANNEL_INDEX><INTERLEAVE_INDEX>1</INTERLEAVE_INDEX><NAME>Boom</NAME></TRACK>\
+        <TRACK><CHANNEL_INDEX>2</CHANNEL_INDEX><INTERLEAVE_INDEX>2</INTERLEAVE_INDEX><NAME>Lav 1</NAME></TRACK>\
+        </TRACK_LIST></BWFXML>";
+    let mut payload = xml.as_bytes().to_vec();
+    payload.extend_from_slice(&[0; 7]);
+
+    let wave = build_wave(&[build_chunk(b"iXML", &payload), build_chunk(b"data", &[0; 16])]);
+    let wave_file = WaveReader::new(Cursor::new(wave)).decode().unwrap();
+    assert_eq!(wave_file.ixml.as_deref(), Some(xml));
+
+    let ixml = wave_file.parse_ixml().unwrap();
+    assert_eq!(ixml.project.as_deref(), Some("Night & Day"));
+    assert_eq!(ixml.scene.as_deref(), Some("12A"));
+    assert_eq!(ixml.take.as_deref(), Some("3"));
+    assert_eq!(ixml.tape.as_deref(), Some("230614"));
+    assert_eq!(ixml.circled, Some(true));
+    assert_eq!(ixml.note.as_deref(), Some("wind"));
+    assert_eq!(ixml.tracks.len(), 2);
+    assert_eq!(ixml.tracks[1].name.as_deref(), Some("Lav 1"));
+    assert_eq!(ixml.tracks[1].channel_index, Some(2));
+
+    let speed = ixml.speed.unwrap();
+    assert_eq!(speed.note.as_deref(), Some("speed note"));
+    assert_eq!(speed.timecode_rate.as_deref(), Some("25/1"));
+    assert_eq!(speed.file_sample_rate, Some(48000));
+    assert_eq!(speed.timestamp(), Some(std::time::Duration::from_secs(36000)));
+}
+
+#[test]
+fn info_fields_round_trip() {
+    let mut list = b"INFO".to_vec();
+    for (id, value) in [(b"INAM", "Title\0"), (b"IPRD", "Album\0"), (b"ITRK", "7\0"), (b"IXYZ", "vendor\0"), (b"ILNG", "de\0"), (b"IPRT", "7/12\0")] {
+        list.extend(build_chunk(id, value.as_bytes()));
+    }
+    let list_chunk = build_chunk(b"LIST", &list);
+
+    let wave = build_wave(&[list_chunk.clone(), build_chunk(b"data", &[0; 16])]);
+    let wave_file = WaveReader::new(Cursor::new(wave)).decode().unwrap();
+    let metadata = &wave_file.metadata;
+    assert_eq!(metadata.title.as_deref(), Some("Title"));
+    assert_eq!(metadata.album.as_deref(), Some("Album"));
+    assert_eq!(metadata.track.as_deref(), Some("7"));
+    assert_eq!(metadata.part.as_deref(), Some("7/12"));
+    #[allow(deprecated)]
+    let name = metadata.name();
+    assert_eq!(name, Some("Title"));
+    assert_eq!(metadata.language.as_deref(), Some("de"));
+    assert_eq!(metadata.info[3], (FourCC(*b"IXYZ"), String::from("vendor")));
+
+    let mut rewritten: Vec<u8> = Vec::new();
+    write_info_list(&mut rewritten, metadata, TextEncoding::Utf8).unwrap();
+    assert_eq!(rewritten, list_chunk);
+
+    let mut edited = metadata.clone();
+    edited.title = None;
+    edited.genre = Some(String::from("Jazz"));
+    let ids: Vec<FourCC> = edited.info_entries().into_iter().map(|(id, _)| id).collect();
+    assert_eq!(ids, [FourCC(*b"IPRD"), FourCC(*b"ITRK"), FourCC(*b"IXYZ"), FourCC(*b"ILNG"), FourCC(*b"IPRT"), FourCC(*b"IGNR")]);
+}
+
+#[test]
+fn info_text_encodings() {
+    let mut list = b"INFO".to_vec();
+    list.extend(build_chunk(b"IART", b"Caf\xe9\0\0\0"));
+    let wave = build_wave(&[build_chunk(b"LIST", &list), build_chunk(b"data", &[0; 16])]);
+
+    // Invalid UTF-8 without CSET chunk is decoded with the fallback encoding
+    let wave_file = WaveReader::new(Cursor::new(wave.clone())).decode().unwrap();
+    assert_eq!(wave_file.metadata.artist.as_deref(), Some("Caf\u{e9}"));
+    let options = DecodeOptions { fallback_encoding: TextEncoding::ShiftJis, ..DecodeOptions::new() };
+    let wave_file = WaveReader::with_options(Cursor::new(wave), options).decode().unwrap();
+    assert_ne!(wave_file.metadata.artist.as_deref(), Some("Caf\u{e9}"));
+
+    // Shift-JIS declared by a CSET chunk following the LIST chunk
+    let mut list = b"INFO".to_vec();
+    list.extend(build_chunk(b"INAM", b"\x93\xfa\x96\x7b\0"));
+    let cset = [932_u16, 81, 17, 1].iter().flat_map(|x| x.to_le_bytes()).collect::<Vec<u8>>();
+    let wave = build_wave(&[build_chunk(b"LIST", &list), build_chunk(b"CSET", &cset), build_chunk(b"data", &[0; 16])]);
+    let wave_file = WaveReader::new(Cursor::new(wave)).decode().unwrap();
+    assert_eq!(wave_file.charset.unwrap().code_page, 932);
+    assert_eq!(wave_file.metadata.title.as_deref(), Some("\u{65e5}\u{672c}"));
+
+    // Strings read directly honor the fallback, sizes beyond the source fail without allocating them.
+    use crate::util::exts::read::SizedDataRead;
+    assert_eq!(Cursor::new(b"\x93\xfa\x96\x7b".to_vec()).read_string(4, TextEncoding::ShiftJis).unwrap(), "\u{65e5}\u{672c}");
+    assert!(Cursor::new(vec![0; 4]).read_bytes(u32::MAX).is_err());
+}
+
+#[test]
+fn raw_chunks_round_trip() {
+    let mut list = b"INFO".to_vec();
+    list.extend(build_chunk(b"IART", b"Artist\0"));
+    let wave = build_wave(&[
+        build_chunk(b"_PMX", b"<xmp/>"),
+        build_chunk(b"LIST", &list),
+        build_chunk(b"minf", &[1, 2, 3]),
+        build_chunk(b"data", &[1, 0, 2, 0, 3, 0, 4, 0]),
+        build_chunk(b"regn", &[9; 12]),
+    ]);
+    let wave_file = WaveReader::new(Cursor::new(wave.clone())).decode().unwrap();
+
+    let ids: Vec<FourCC> = wave_file.chunks().iter().map(|x| x.id).collect();
+    assert_eq!(ids, [FourCC(*b"fmt "), FourCC(*b"_PMX"), FourCC(*b"LIST"), FourCC(*b"minf"), FourCC(*b"data"), FourCC(*b"regn")]);
+    let minf = wave_file.chunk(FourCC(*b"minf")).unwrap();
+    assert_eq!((minf.offset, minf.size), (78, 3));
+    assert_eq!(wave_file.chunk_data(minf), [1, 2, 3]);
+    assert_eq!(wave_file.chunk_data(wave_file.chunk(FourCC(*b"data")).unwrap()), [1, 0, 2, 0, 3, 0, 4, 0]);
+    assert_eq!(wave_file.chunk_data(wave_file.chunk(FourCC(*b"regn")).unwrap()), [9; 12]);
+
+    let mut writer = WaveWriter::new(Vec::new());
+    writer.encode(&wave_file).unwrap();
+    assert_eq!(writer.into_inner(), wave);
+}
+
+struct PmxHandler;
+
+impl ChunkHandler for PmxHandler {
+    type Output = String;
+
+    fn id(&self) -> FourCC {
+        FourCC(*b"_PMX")
+    }
+
+    fn parse(&self, data: &[u8]) -> Result<String, WaveDecodeError> {
+        Ok( String::from_utf8_lossy(data).into_owned() )
+    }
+}
+
+#[test]
+fn custom_chunk_handler() {
+    let wave = build_wave(&[build_chunk(b"_PMX", b"<xmp/>"), build_chunk(b"data", &[0; 4])]);
+    let mut reader = WaveReader::new(Cursor::new(wave));
+    reader.register_handler(PmxHandler);
+    let wave_file = reader.decode().unwrap();
+
+    assert_eq!(wave_file.get_chunk::<String>().map(|x| x.as_str()), Some("<xmp/>"));
+    assert_eq!(wave_file.get_chunks::<String>()[0].0, FourCC(*b"_PMX"));
+    assert!(wave_file.get_chunk::<u32>().is_none());
+    // Handled chunks are still retained as raw chunk
+    assert!(wave_file.chunk(FourCC(*b"_PMX")).is_some());
+
+    // Parsed chunks must not keep decoded files from being moved across threads.
+    fn assert_send_sync<T: Send + Sync>(_: &T) {}
+    assert_send_sync(&wave_file);
+}
+
+#[test]
+fn edit_metadata_in_place() {
+    let mut list = b"INFO".to_vec();
+    list.extend(build_chunk(b"INAM", b"A rather long title\0"));
+    let samples: Vec<u8> = (0..64).collect();
+    let wave = build_wave(&[build_chunk(b"LIST", &list), build_chunk(b"data", &samples)]);
+    let data_offset = wave.len() - samples.len() - 8;
+
+    // Shorter metadata is written in front of the data chunk, padded with JUNK
+    let mut editor = WaveEditor::open(Cursor::new(wave)).unwrap();
+    editor.wave_file_mut().metadata.title = Some(String::from("Short"));
+    editor.save().unwrap();
+    let wave = editor.into_inner().into_inner();
+    assert_eq!(&wave[(data_offset + 8)..(data_offset + 72)], &samples[..]);
+    let wave_file = WaveReader::new(Cursor::new(wave.clone())).decode().unwrap();
+    assert_eq!(wave_file.metadata.title.as_deref(), Some("Short"));
+    assert!(wave_file.chunk(FourCC(*b"JUNK")).is_some());
+    assert_eq!(wave_file.chunk_data(wave_file.chunk(FourCC(*b"data")).unwrap()), samples);
+
+    // Metadata that does not fit anymore is moved behind the data chunk
+    let mut editor = WaveEditor::open(Cursor::new(wave)).unwrap();
+    editor.wave_file_mut().metadata.comments = Some("x".repeat(100));
+    editor.wave_file_mut().cue_points.push(CuePoint::new(1, 8));
+    editor.save().unwrap();
+    assert_eq!(editor.wave_file().chunks().last().unwrap().id, FourCC(*b"cue "));
+    let wave = editor.into_inner().into_inner();
+    assert_eq!(&wave[(data_offset + 8)..(data_offset + 72)], &samples[..]);
+    let wave_file = WaveReader::new(Cursor::new(wave)).decode().unwrap();
+    assert_eq!(wave_file.metadata.title.as_deref(), Some("Short"));
+    assert_eq!(wave_file.metadata.comments.as_deref(), Some("x".repeat(100).as_str()));
+    assert_eq!(wave_file.cue_points, [CuePoint::new(1, 8)]);
+
+    // Lists other than INFO, e.g. cue labels, do not prevent editing and are kept.
+    let mut adtl = b"adtl".to_vec();
+    adtl.extend(build_chunk(b"labl", b"\x01\0\0\0Intro\0"));
+    let wave = build_wave(&[build_chunk(b"LIST", &adtl), build_chunk(b"data", &samples)]);
+    let mut editor = WaveEditor::open(Cursor::new(wave)).unwrap();
+    editor.wave_file_mut().metadata.title = Some(String::from("Labeled"));
+    editor.save().unwrap();
+    let wave = editor.into_inner().into_inner();
+    assert!(wave.windows(adtl.len()).any(|x| x == &adtl[..]));
+    let wave_file = WaveReader::with_options(Cursor::new(wave), DecodeOptions::lenient()).decode().unwrap();
+    assert_eq!(wave_file.metadata.title.as_deref(), Some("Labeled"));
+
+    // Unfinished recordings are refused instead of being extended with zeros or read as chunks.
+    let mut truncated = build_wave(&[build_chunk(b"data", &samples)]);
+    truncated[40..44].copy_from_slice(&1000_u32.to_le_bytes());
+    assert!(matches!(WaveEditor::open(Cursor::new(truncated)), Err(WaveDecodeError::Unsupported(_))));
+    let mut streamed = build_wave(&[build_chunk(b"data", &samples)]);
+    streamed[4..8].copy_from_slice(&0_u32.to_le_bytes());
+    streamed[40..44].copy_from_slice(&0_u32.to_le_bytes());
+    assert!(matches!(WaveEditor::open(Cursor::new(streamed)), Err(WaveDecodeError::Unsupported(_))));
+}
+
+#[test]
+fn structured_errors() {
+    let err = WaveReader::new(Cursor::new(b"RIFF".to_vec())).decode().unwrap_err();
+    assert!(matches!(err, WaveDecodeError::InvalidHeader));
+
+    // An acid chunk is 24 bytes long, the error carries id and offset of the broken chunk.
+    let bytes = build_wave(&[build_chunk(b"acid", &[0; 10]), build_chunk(b"data", &[0; 4])]);
+    match WaveReader::new(Cursor::new(bytes)).decode() {
+        Err(WaveDecodeError::InvalidChunk { id, offset, .. }) => {
+            assert_eq!(id, FourCC(*b"acid"));
+            assert_eq!(offset, 36);
+        },
+        x => panic!("Unexpected result {:?}", x.map(|_| ())),
+    };
+
+    let wave_file = WaveReader::new(Cursor::new(build_wave(&[build_chunk(b"data", &[0; 4])]))).decode().unwrap();
+    assert!(matches!(wave_file.samples::<u8>(), Err(WaveDecodeError::IncompatibleSampleType { bits: 16, .. })));
+}
+
+#[test]
+fn decode_warnings() {
+    use std::rc::Rc;
+    use std::cell::RefCell;
+    use crate::DecodeWarning;
+
+    let mut bytes = build_wave(&[build_chunk(b"zzzz", &[1, 2]), build_chunk(b"data", &[0; 4])]);
+    bytes.extend_from_slice(&[0; 3]);
+    let riff_size = (bytes.len() - 8) as u32;
+    bytes[4..8].copy_from_slice(&riff_size.to_le_bytes());
+
+    let seen: Rc<RefCell<Vec<DecodeWarning>>> = Rc::new(RefCell::new(Vec::new()));
+    let sink = seen.clone();
+    let mut reader = WaveReader::new(Cursor::new(bytes));
+    reader.on_warning(move |x| sink.borrow_mut().push(x.clone()));
+    let wave_file = reader.decode().unwrap();
+
+    assert_eq!(wave_file.warnings, vec![
+        DecodeWarning::UnknownChunk { id: FourCC(*b"zzzz"), offset: 36 },
+        DecodeWarning::TrailingBytes { offset: 58, len: 3 },
+    ]);
+    assert_eq!(*seen.borrow(), wave_file.warnings);
+}
+
+#[test]
+fn lenient_decoding() {
+    use crate::DecodeWarning;
+
+    // Streamed file whose RIFF and data sizes were never written, the last frame is incomplete
+    let mut bytes = build_wave(&[build_chunk(b"LIST", b"adtl"), build_chunk(b"data", &[1; 14])]);
+    bytes[4..8].copy_from_slice(&u32::MAX.to_le_bytes());
+    bytes[52..56].copy_from_slice(&0_u32.to_le_bytes());
+    assert!(matches!(WaveReader::new(Cursor::new(bytes.clone())).decode(), Err(WaveDecodeError::SizeMismatch { .. })));
+
+    let wave_file = WaveReader::with_options(Cursor::new(bytes), DecodeOptions::lenient()).decode().unwrap();
+    assert_eq!(wave_file.samples::<i16>().unwrap().len(), 6);
+    assert_eq!(wave_file.warnings, vec![
+        DecodeWarning::SizeMismatch { declared: (u32::MAX as u64) + 8, actual: 70 },
+        DecodeWarning::UnknownListType { list_type: FourCC(*b"adtl"), offset: 36 },
+        DecodeWarning::DataClamped { offset: 48, declared: 0, actual: 12 },
+    ]);
+
+    // File that was cut off within the data chunk
+    let mut bytes = build_wave(&[build_chunk(b"data", &[1; 16])]);
+    bytes.truncate(bytes.len() - 6);
+    let wave_file = WaveReader::with_options(Cursor::new(bytes), DecodeOptions::lenient()).decode().unwrap();
+    assert_eq!(wave_file.chunk_data(wave_file.chunk(FourCC(*b"data")).unwrap()).len(), 8);
+}
+
+#[test]
+fn validation_report() {
+    use crate::validate::{validate, IssueKind, Severity};
+
+    let wave = build_wave(&[build_chunk(b"data", &[0; 16])]);
+    assert!(validate(Cursor::new(wave)).unwrap().issues.is_empty());
+
+    // Data rate and channel mask do not match, the odd-sized chunk lacks its pad byte and garbage follows
+    let mut fmt: Vec<u8> = Vec::new();
+    for x in [0xfffe_u16, 2] { fmt.extend_from_slice(&x.to_le_bytes()); }
+    for x in [48000_u32, 44100 * 4] { fmt.extend_from_slice(&x.to_le_bytes()); }
+    for x in [4_u16, 16, 22, 16] { fmt.extend_from_slice(&x.to_le_bytes()); }
+    fmt.extend_from_slice(&0x7_u32.to_le_bytes());
+    fmt.extend_from_slice(&[0; 16]);
+    let mut bytes = b"RIFF\0\0\0\0WAVE".to_vec();
+    bytes.extend(build_chunk(b"fmt ", &fmt));
+    bytes.extend(build_chunk(b"fact", &9_u32.to_le_bytes()));
+    bytes.extend_from_slice(b"zzzz\x03\0\0\0abc");
+    bytes.extend(build_chunk(b"data", &[0; 16]));
+    let riff_size = (bytes.len() - 8) as u32;
+    bytes[4..8].copy_from_slice(&riff_size.to_le_bytes());
+    bytes.extend_from_slice(&[0xff; 5]);
+
+    let report = validate(Cursor::new(bytes)).unwrap();
+    let kinds: Vec<(Severity, Option<u64>, IssueKind)> = report.issues.iter()
+        .map(|x| (x.severity, x.offset, x.kind.clone()))
+        .collect();
+    assert_eq!(kinds, vec![
+        (Severity::Warning, Some(72), IssueKind::MissingPadByte { id: FourCC(*b"zzzz") }),
+        (Severity::Error, Some(83), IssueKind::OddAlignedChunk { id: FourCC(*b"data") }),
+        (Severity::Warning, Some(107), IssueKind::TrailingGarbage { len: 5 }),
+        (Severity::Warning, Some(12), IssueKind::DataRateMismatch { declared: 44100 * 4, expected: 48000 * 4 }),
+        (Severity::Warning, Some(60), IssueKind::FactMismatch { declared: 9, actual: 4 }),
+        (Severity::Warning, Some(12), IssueKind::ChannelMaskMismatch { channels: 2, mask_channels: 3 }),
+        (Severity::Info, Some(72), IssueKind::UnknownChunk { id: FourCC(*b"zzzz") }),
+    ]);
+    assert!(!report.is_valid());
+
+    // Issues only the reader detects, the data chunk exceeds the source and is clamped
+    let mut bytes = build_wave(&[build_chunk(b"LIST", b"adtl"), build_chunk(b"data", &[0; 16])]);
+    bytes[52..56].copy_from_slice(&20_u32.to_le_bytes());
+    let report = validate(Cursor::new(bytes)).unwrap();
+    let kinds: Vec<(Severity, Option<u64>, IssueKind)> = report.issues.iter()
+        .map(|x| (x.severity, x.offset, x.kind.clone()))
+        .collect();
+    assert_eq!(kinds, vec![
+        (Severity::Error, Some(48), IssueKind::TruncatedChunk { id: FourCC(*b"data") }),
+        (Severity::Info, Some(36), IssueKind::UnknownListType { list_type: FourCC(*b"adtl") }),
+        (Severity::Warning, Some(48), IssueKind::DataClamped { declared: 20, actual: 16 }),
+    ]);
+
+    // Frame size and data rate that do not fit into the fields of the fmt chunk, a repeated cue chunk
+    let mut fmt: Vec<u8> = Vec::new();
+    for x in [1_u16, 0xffff] { fmt.extend_from_slice(&x.to_le_bytes()); }
+    for x in [96000_u32, 0xffff_ffff] { fmt.extend_from_slice(&x.to_le_bytes()); }
+    for x in [0xffff_u16, 64] { fmt.extend_from_slice(&x.to_le_bytes()); }
+    let mut bytes = b"RIFF\0\0\0\0WAVE".to_vec();
+    bytes.extend(build_chunk(b"fmt ", &fmt));
+    bytes.extend(build_chunk(b"cue ", &[0; 4]));
+    bytes.extend(build_chunk(b"cue ", &[0; 4]));
+    bytes.extend(build_chunk(b"data", &[]));
+    let riff_size = (bytes.len() - 8) as u32;
+    bytes[4..8].copy_from_slice(&riff_size.to_le_bytes());
+    let report = validate(Cursor::new(bytes)).unwrap();
+    let kinds: Vec<(Severity, Option<u64>, IssueKind)> = report.issues.iter()
+        .map(|x| (x.severity, x.offset, x.kind.clone()))
+        .collect();
+    assert_eq!(kinds, vec![
+        (Severity::Warning, Some(48), IssueKind::DuplicateChunk { id: FourCC(*b"cue ") }),
+        (Severity::Error, Some(12), IssueKind::FrameSizeMismatch { declared: 0xffff, expected: 0xffff * 8 }),
+        (Severity::Warning, Some(12), IssueKind::DataRateMismatch { declared: u32::MAX, expected: 96000 * 0xffff }),
+    ]);
+}
+
+#[test]
+fn repair_unfinished_recording() {
+    use crate::repair::{repair, repair_copy};
+
+    // Header sizes were never written, the recording stopped within a frame
+    let mut bytes = build_wave(&[build_chunk(b"data", &[1; 16])]);
+    bytes.truncate(bytes.len() - 2);
+    bytes[4..8].copy_from_slice(&0_u32.to_le_bytes());
+    bytes[40..44].copy_from_slice(&0x7ffff000_u32.to_le_bytes());
+
+    let mut copy: Vec<u8> = Vec::new();
+    repair_copy(Cursor::new(bytes.clone()), &mut copy).unwrap();
+
+    let mut file = Cursor::new(bytes);
+    let report = repair(&mut file).unwrap();
+    assert_eq!((report.old_data_size, report.new_data_size, report.removed_bytes), (0x7ffff000, 12, 2));
+    assert_eq!(report.new_riff_size, 48);
+    assert!(!repair(&mut file).unwrap().changed());
+
+    let wave_file = WaveReader::new(Cursor::new(file.into_inner())).decode().unwrap();
+    assert_eq!(wave_file.samples::<i16>().unwrap(), vec![0x0101; 6]);
+    assert_eq!(WaveReader::new(Cursor::new(copy)).decode().unwrap().samples::<i16>().unwrap(), vec![0x0101; 6]);
+
+    // An empty data chunk followed by metadata is left alone.
+    let bytes = build_wave(&[build_chunk(b"data", &[]), build_chunk(b"LIST", b"INFOINAM\x04\0\0\0Song")]);
+    let mut file = Cursor::new(bytes.clone());
+    assert!(!repair(&mut file).unwrap().changed());
+    assert_eq!(file.into_inner(), bytes);
+
+    // Even if the header was never finalized, only the RIFF size is fixed then.
+    let mut unfinished = bytes.clone();
+    unfinished[4..8].copy_from_slice(&0_u32.to_le_bytes());
+    let mut file = Cursor::new(unfinished);
+    let report = repair(&mut file).unwrap();
+    assert_eq!((report.old_data_size, report.new_data_size, report.new_riff_size), (0, 0, bytes.len() as u32 - 8));
+    assert_eq!(file.into_inner(), bytes);
+}
+
+#[test]
+fn raw_pcm_round_trip() {
+    use crate::WaveFormat;
+    use crate::raw::{RawPcmReader, RawPcmSpec, RawPcmWriter};
+    use crate::util::exts::Endian;
+
+    // Big-endian 16-bit stereo with an incomplete frame at the end
+    let spec = RawPcmSpec { endian: Endian::Big, ..RawPcmSpec::new(WaveFormat::Pcm, 2, 8000, 16) };
+    let raw = vec![0x12, 0x34, 0xff, 0xfe, 0x80, 0x00, 0x7f, 0xff, 0x01];
+    let wave_file = RawPcmReader::new(Cursor::new(raw.clone()), spec).decode().unwrap();
+    assert_eq!(wave_file.samples::<i16>().unwrap(), vec![0x1234, -2, i16::MIN, i16::MAX]);
+    assert_eq!(wave_file.warnings.len(), 1);
+
+    let mut writer = RawPcmWriter::new(Vec::new(), spec);
+    writer.encode(&wave_file).unwrap();
+    assert_eq!(writer.into_inner(), raw[..8].to_vec());
+
+    let mut writer = WaveWriter::new(Vec::new());
+    writer.encode(&wave_file).unwrap();
+    let wave_file = WaveReader::new(Cursor::new(writer.into_inner())).decode().unwrap();
+    assert_eq!((wave_file.channels, wave_file.sample_rate, wave_file.data_rate), (2, 8000, 32000));
+    assert_eq!(wave_file.samples::<i16>().unwrap(), vec![0x1234, -2, i16::MIN, i16::MAX]);
+
+    // Signed 8-bit is stored unsigned in WAVE
+    let spec = RawPcmSpec { signed: true, ..RawPcmSpec::new(WaveFormat::Pcm, 1, 8000, 8) };
+    let wave_file = RawPcmReader::new(Cursor::new(vec![0x00, 0x7f, 0x80]), spec).decode().unwrap();
+    assert_eq!(wave_file.chunk_data(&crate::chunks::Chunk::new(FourCC(*b"data"), vec![])), &[0x80, 0xff, 0x00]);
+    let mut writer = RawPcmWriter::new(Vec::new(), spec);
+    writer.encode(&wave_file).unwrap();
+    assert_eq!(writer.into_inner(), vec![0x00, 0x7f, 0x80]);
+    assert!(RawPcmWriter::new(Vec::new(), RawPcmSpec::new(WaveFormat::Pcm, 1, 8000, 16)).encode(&wave_file).is_err());
+
+    // Header values of other containers are untrusted, data rates beyond 32 bits are rejected.
+    let spec = RawPcmSpec::new(WaveFormat::Pcm, 2, 4_000_000_000, 16);
+    assert!(matches!(RawPcmReader::new(Cursor::new(vec![0; 4]), spec).decode(), Err(WaveDecodeError::Unsupported(_))));
+}
+
+#[test]
+fn au_round_trip() {
+    use crate::WaveFormat;
+    use crate::au::{AuReader, AuWriter};
+    use crate::util::g711::{expand_alaw, expand_mulaw};
+
+    assert_eq!((expand_mulaw(0xff), expand_mulaw(0x80), expand_mulaw(0x00)), (0, 32124, -32124));
+    assert_eq!((expand_alaw(0xd5), expand_alaw(0x55), expand_alaw(0xaa)), (8, -8, 32256));
+
+    // 16-bit mono with annotation and unknown data size
+    let mut bytes: Vec<u8> = Vec::new();
+    for x in [0x2e736e64_u32, 32, 0xffffffff, 3, 8000, 1] { bytes.extend_from_slice(&x.to_be_bytes()); }
+    bytes.extend_from_slice(b"take 1\0\0");
+    bytes.extend_from_slice(&[0x12, 0x34, 0x80, 0x00]);
+    let wave_file = AuReader::new(Cursor::new(bytes.clone())).decode().unwrap();
+    assert_eq!((wave_file.format, wave_file.channels, wave_file.sample_rate), (WaveFormat::Pcm, 1, 8000));
+    assert_eq!(wave_file.metadata.comments.as_deref(), Some("take 1"));
+    assert_eq!(wave_file.samples::<i16>().unwrap(), vec![0x1234, i16::MIN]);
+
+    let mut writer = AuWriter::new(Vec::new());
+    writer.encode(&wave_file).unwrap();
+    let mut expected = bytes;
+    expected[8..12].copy_from_slice(&4_u32.to_be_bytes());
+    assert_eq!(writer.into_inner(), expected);
+
+    // µ-law is kept companded and expanded by the sample API
+    let mut bytes: Vec<u8> = Vec::new();
+    for x in [0x2e736e64_u32, 28, 2, 1, 8000, 1, 0] { bytes.extend_from_slice(&x.to_be_bytes()); }
+    bytes.extend_from_slice(&[0xff, 0x80]);
+    let wave_file = AuReader::new(Cursor::new(bytes)).decode().unwrap();
+    assert_eq!(wave_file.format, WaveFormat::Mulaw);
+    assert_eq!(wave_file.samples::<i16>().unwrap(), vec![0, 32124]);
+}
+
+#[test]
+fn caf_lpcm() {
+    use crate::caf::CafReader;
+
+    fn caf_chunk(id: &[u8; 4], payload: &[u8]) -> Vec<u8> {
+        let mut chunk = id.to_vec();
+        chunk.extend_from_slice(&(payload.len() as i64).to_be_bytes());
+        chunk.extend_from_slice(payload);
+        chunk
+    }
+
+    // Little-endian 16-bit stereo with stereo channel bitmap and info strings
+    let mut desc = 48000_f64.to_be_bytes().to_vec();
+    desc.extend_from_slice(b"lpcm");
+    for x in [0x2_u32, 4, 1, 2, 16] { desc.extend_from_slice(&x.to_be_bytes()); }
+    let mut chan: Vec<u8> = Vec::new();
+    for x in [0x10000_u32, 0x3, 0] { chan.extend_from_slice(&x.to_be_bytes()); }
+    let mut info = 2_u32.to_be_bytes().to_vec();
+    info.extend_from_slice(b"title\0Field recording\0composer\0Someone\0");
+    let mut data = 7_u32.to_be_bytes().to_vec();
+    data.extend_from_slice(&[0x34, 0x12, 0xfe, 0xff, 0x00, 0x80, 0xff, 0x7f]);
+
+    let mut bytes = b"caff\0\x01\0\0".to_vec();
+    for chunk in [caf_chunk(b"desc", &desc), caf_chunk(b"chan", &chan), caf_chunk(b"info", &info)] {
+        bytes.extend(chunk);
+    }
+    bytes.extend_from_slice(b"data");
+    bytes.extend_from_slice(&(-1_i64).to_be_bytes());
+    bytes.extend(data);
+
+    let caf_file = CafReader::new(Cursor::new(bytes)).decode().unwrap();
+    assert_eq!(caf_file.edit_count, 7);
+    assert_eq!(caf_file.info.len(), 2);
+    let wave_file = &caf_file.wave_file;
+    assert_eq!((wave_file.channels, wave_file.sample_rate, wave_file.channel_mask), (2, 48000, Some(0x3)));
+    assert_eq!(wave_file.metadata.title.as_deref(), Some("Field recording"));
+    assert_eq!(wave_file.samples::<i16>().unwrap(), vec![0x1234, -2, i16::MIN, i16::MAX]);
+}
+
+#[test]
+fn probe_containers() {
+    use crate::probe::{detect, probe, Container};
+
+    let wave = build_wave(&[build_chunk(b"data", &[0; 4])]);
+    assert_eq!(detect(&wave), Some(Container::Wave));
+    assert_eq!(probe(Cursor::new(wave)).unwrap().channels, 2);
+
+    let mut au: Vec<u8> = Vec::new();
+    for x in [0x2e736e64_u32, 24, 2, 3, 8000, 1] { au.extend_from_slice(&x.to_be_bytes()); }
+    au.extend_from_slice(&[0x12, 0x34]);
+    assert_eq!(probe(Cursor::new(au)).unwrap().samples::<i16>().unwrap(), vec![0x1234]);
+
+    assert_eq!(detect(b"FORM\0\0\0\x04AIFF"), Some(Container::Aiff));
+    assert_eq!(detect(b"RF64\xff\xff\xff\xffWAVE"), Some(Container::Rf64));
+    assert!(matches!(probe(Cursor::new(b"FORM\0\0\0\x04AIFF".to_vec())), Err(WaveDecodeError::Unsupported(_))));
+    assert!(matches!(probe(Cursor::new(b"OggS".to_vec())), Err(WaveDecodeError::InvalidHeader)));
+}
+
+#[test]
+fn sample_write() {
+    use crate::{Sample, WaveFile, WaveFormat};
+
+    fn encode<S: Sample>(sample: S, format: WaveFormat, bits: u16) -> Vec<u8> {
+        let mut buf: Vec<u8> = Vec::new();
+        sample.write(&mut buf, format, bits).unwrap();
+        buf
+    }
+
+    // Full scale maps onto the limits of every depth, out of range values are clipped.
+    assert_eq!(encode(-1.0_f32, WaveFormat::Pcm, 8), vec![0]);
+    assert_eq!(encode(1.0_f32, WaveFormat::Pcm, 8), vec![255]);
+    assert_eq!(encode(0.0_f32, WaveFormat::Pcm, 8), vec![128]);
+    assert_eq!(encode(1.5_f32, WaveFormat::Pcm, 16), i16::MAX.to_le_bytes().to_vec());
+    assert_eq!(encode(-2.0_f64, WaveFormat::Pcm, 24), vec![0x00, 0x00, 0x80]);
+    assert_eq!(encode(f64::NAN, WaveFormat::Pcm, 32), vec![0; 4]);
+    assert_eq!(encode(i64::MIN, WaveFormat::Pcm, 64), i64::MIN.to_le_bytes().to_vec());
+    assert_eq!(encode(i16::MIN, WaveFormat::Pcm, 32), i32::MIN.to_le_bytes().to_vec());
+    assert_eq!(encode(i32::MIN, WaveFormat::Pcm, 24), vec![0x00, 0x00, 0x80]);
+    assert_eq!(encode(0x12345600_i32, WaveFormat::Pcm, 24), vec![0x56, 0x34, 0x12]);
+    assert_eq!(encode(i32::MAX, WaveFormat::Pcm, 24), vec![0xff, 0xff, 0x7f]);
+    assert_eq!(encode(255_u8, WaveFormat::Pcm, 16), i16::MAX.to_le_bytes().to_vec());
+    assert_eq!(encode(0.5_f64, WaveFormat::Pcm, 16), 16384_i16.to_le_bytes().to_vec());
+    assert_eq!(encode(1.5_f32, WaveFormat::IeeeFloat, 32), 1.5_f32.to_le_bytes().to_vec());
+
+    // Samples of the stored type survive a round trip unchanged.
+    let samples: Vec<i16> = vec![i16::MIN, -1, 0, 1, i16::MAX, 12345];
+    let wave_file = WaveFile::from_samples(&samples, 2, 48000, WaveFormat::Pcm, 16).unwrap();
+    assert_eq!(wave_file.frame_size(), 4);
+    assert_eq!(wave_file.data_rate, 192000);
+    let mut buf: Vec<u8> = Vec::new();
+    WaveWriter::new(&mut buf).encode(&wave_file).unwrap();
+    assert_eq!(WaveReader::new(Cursor::new(buf)).decode().unwrap().samples::<i16>().unwrap(), samples);
+
+    let samples: Vec<f32> = vec![-1.0, -0.25, 0.0, 0.75];
+    let wave_file = WaveFile::from_samples(&samples, 1, 8000, WaveFormat::IeeeFloat, 32).unwrap();
+    assert_eq!(wave_file.samples::<f32>().unwrap(), samples);
+    let wave_file = WaveFile::from_samples(&[0_u8, 64, 128, 255], 1, 8000, WaveFormat::Pcm, 8).unwrap();
+    assert_eq!(wave_file.samples::<u8>().unwrap(), vec![0, 64, 128, 255]);
+
+    // i32 is left-justified at every depth, so changing the bit depth keeps the level.
+    let samples: Vec<i32> = vec![i32::MAX, i32::MIN, 0, -0x12345600];
+    let wave_file = WaveFile::from_samples(&samples, 1, 48000, WaveFormat::Pcm, 24).unwrap();
+    assert_eq!(wave_file.sample_data[9..12], [0xaa, 0xcb, 0xed]);
+    assert_eq!(wave_file.samples::<i32>().unwrap(), samples);
+    let wave_file = WaveFile::from_samples(&wave_file.samples::<i32>().unwrap(), 1, 48000, WaveFormat::Pcm, 16).unwrap();
+    assert_eq!(wave_file.samples::<i16>().unwrap(), vec![i16::MAX, i16::MIN, 0, -0x1234]);
+
+    assert!(matches!(WaveFile::from_samples(&[0_i16; 3], 2, 8000, WaveFormat::Pcm, 16), Err(WaveDecodeError::Unsupported(_))));
+    assert!(matches!(WaveFile::from_samples(&[0_i16], 1, 8000, WaveFormat::Pcm, 12), Err(WaveDecodeError::UnsupportedBitDepth { .. })));
+    assert!(matches!(WaveFile::from_samples(&[0_i16], 1, 8000, WaveFormat::Alaw, 8), Err(WaveDecodeError::UnsupportedFormat(_))));
+    assert!(matches!(WaveFile::from_samples::<i16>(&[], 1, 8000, WaveFormat::Pcm, 12), Err(WaveDecodeError::UnsupportedBitDepth { .. })));
+    assert!(matches!(WaveFile::from_samples::<i16>(&[], 40000, 8000, WaveFormat::Pcm, 16), Err(WaveDecodeError::Unsupported(_))));
+    assert!(matches!(WaveFile::from_samples::<i16>(&[], 2, 4_000_000_000, WaveFormat::Pcm, 16), Err(WaveDecodeError::Unsupported(_))));
+}
+
+#[test]
+fn bulk_decoding() {
+    use crate::{Sample, WaveFormat};
+
+    // Bulk and per-sample decoding agree for every depth.
+    let data: Vec<u8> = (0..=255).collect();
+    for (format, bits) in [(WaveFormat::Pcm, 8), (WaveFormat::Pcm, 16), (WaveFormat::Pcm, 24), (WaveFormat::Pcm, 32), (WaveFormat::Pcm, 64), (WaveFormat::Mulaw, 8)] {
+        let mut bulk = vec![0_f64; 256];
+        let len = f64::read_slice(&data, format, bits, &mut bulk).unwrap();
+        assert_eq!(len, 256 / (bits as usize / 8));
+
+        let mut cursor = Cursor::new(data[..(len * bits as usize / 8)].to_vec());
+        let single: Vec<f64> = (0..len).map(|_| f64::read(&mut cursor, format, bits).unwrap()).collect();
+        assert_eq!(&bulk[..len], &single[..]);
+    }
+
+    // Decoding stops at the shorter of both slices, an incomplete sample is ignored.
+    let mut out = [0_i16; 2];
+    assert_eq!(i16::read_slice(&[1, 0, 2, 0, 3, 0], WaveFormat::Pcm, 16, &mut out).unwrap(), 2);
+    assert_eq!(out, [1, 2]);
+    let mut out = [0_i32; 4];
+    assert_eq!(i32::read_slice(&[0xff, 0xff, 0xff, 0x00, 0x00, 0x80, 0x01], WaveFormat::Pcm, 24, &mut out).unwrap(), 2);
+    assert_eq!(&out[..2], &[-256, i32::MIN]);
+
+    assert!(matches!(i16::read_slice(&[], WaveFormat::Pcm, 24, &mut []), Err(WaveDecodeError::IncompatibleSampleType { .. })));
+    assert!(matches!(f32::read(&mut Cursor::new(vec![0; 4]), WaveFormat::Pcm, 12), Err(WaveDecodeError::UnsupportedBitDepth { .. })));
+}
+
+#[test]
+fn borrowed_sample_views() {
+    use crate::{Sample, WaveFile, WaveFormat};
+    use std::borrow::Cow;
+
+    let samples: Vec<i16> = vec![-32768, -1, 0, 1, 32767, 4660];
+    let wave_file = WaveFile::from_samples(&samples, 2, 44100, WaveFormat::Pcm, 16).unwrap();
+    let view = wave_file.samples_view::<i16>().unwrap();
+    assert_eq!(&view[..], &samples[..]);
+    if cfg!(target_endian = "little") {
+        assert!(matches!(view, Cow::Borrowed(_)));
+    }
+
+    // Other types and formats are converted.
+    assert!(matches!(wave_file.samples_view::<f32>().unwrap(), Cow::Owned(_)));
+    let floats: Vec<f32> = vec![-1.0, 0.5, 0.25];
+    let wave_file = WaveFile::from_samples(&floats, 1, 44100, WaveFormat::IeeeFloat, 32).unwrap();
+    assert_eq!(&wave_file.samples_view::<f32>().unwrap()[..], &floats[..]);
+    assert!(matches!(wave_file.samples_view::<f64>().unwrap(), Cow::Owned(_)));
+
+    // Unaligned data cannot be borrowed.
+    let data: Vec<u8> = vec![0; 9];
+    let aligned = match data.as_ptr() as usize % 2 { 0 => 0, _ => 1 };
+    assert!(i16::view(&data[(aligned + 1)..], WaveFormat::Pcm, 16).is_none());
+    assert_eq!(i16::view(&data[aligned..(aligned + 5)], WaveFormat::Pcm, 16).map(|x| x.len()), match cfg!(target_endian = "little") { true => Some(2), false => None });
+    assert!(i16::view(&data, WaveFormat::Pcm, 24).is_none());
+}
+
+#[cfg(feature = "mmap")]
+#[test]
+fn memory_mapped_file() {
+    use crate::mmap::MappedWaveFile;
+    use std::borrow::Cow;
+
+    let samples: Vec<u8> = vec![1, 0, 2, 0, 0xff, 0xff, 0, 0x80];
+    let wave = build_wave(&[build_chunk(b"data", &samples), build_chunk(b"ICMT", b"ab")]);
+    let path = std::env::temp_dir().join(format!("wavers_mmap_{}.wav", std::process::id()));
+    std::fs::write(&path, &wave).unwrap();
+
+    let mapped = unsafe { MappedWaveFile::open(&path) }.unwrap();
+    assert_eq!(mapped.sample_data(), &samples[..]);
+    assert_eq!(mapped.num_of_samples(), 4);
+    assert_eq!(mapped.wave_file().chunks().len(), 3);
+    assert_eq!(mapped.samples::<i16>().unwrap(), vec![1, 2, -1, -32768]);
+    // The data chunk starts at offset 44, so the samples are aligned for i16.
+    assert_eq!(&mapped.samples_view::<i16>().unwrap()[..], &[1, 2, -1, -32768]);
+    if cfg!(target_endian = "little") {
+        assert!(matches!(mapped.samples_view::<i16>().unwrap(), Cow::Borrowed(_)));
+    }
+
+    assert_eq!(mapped.wave_file().chunk_data(mapped.wave_file().chunk(FourCC(*b"ICMT")).unwrap()), b"ab");
+
+    let wave_file = mapped.into_wave_file();
+    assert_eq!(wave_file.chunk_data(wave_file.chunk(FourCC(*b"ICMT")).unwrap()), b"ab");
+    assert_eq!(wave_file.samples::<i16>().unwrap(), WaveReader::new(Cursor::new(wave)).decode().unwrap().samples::<i16>().unwrap());
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn scaling_policies() {
+    use crate::{Sample, WaveFile, WaveFormat};
+    use crate::util::math::{self, Scaling};
+
+    // Integer maps are exact, no precision is lost through f32.
+    assert_eq!(math::map_i16_to_i64(i16::MAX), i64::MAX);
+    assert_eq!(math::map_i16_to_i64(i16::MIN), i64::MIN);
+    assert_eq!(math::map_i16_to_i64(1), 281483566907400);
+    assert_eq!(math::map_i24_to_i64(-1), -(1_i64 << 40));
+    assert_eq!(math::map_i32_to_i64(i32::MAX), i64::MAX);
+
+    assert_eq!(math::map_int_to_f64(i16::MAX as i64, 16, Scaling::Asymmetric), 1.0);
+    assert_eq!(math::map_int_to_f64(i16::MAX as i64, 16, Scaling::Symmetric), 32767.0 / 32768.0);
+    assert_eq!(math::map_f64_to_int(1.0, 16, Scaling::Symmetric), 32767);
+    assert_eq!(math::map_int_to_int(0x1234, 16, 24, Scaling::Shift), 0x123400);
+    assert_eq!(math::map_int_to_int(0x1234ff, 24, 16, Scaling::Shift), 0x1234);
+    assert_eq!(math::map_int_to_int(0x1234ff, 24, 16, Scaling::Symmetric), 0x1235);
+    assert_eq!(math::map_int_to_int(0x7fffff, 24, 16, Scaling::Symmetric), 0x7fff);
+    assert_eq!(math::map_int_to_int(i16::MAX as i64, 16, 24, Scaling::Asymmetric), 0x7fffff);
+
+    // Every value of 8, 16 and 24 bits survives int -> f32 -> int with symmetric scaling.
+    for (bits, step) in [(8_u16, 1_usize), (16, 1), (24, 7)] {
+        let (min, max) = (-(1_i64 << (bits - 1)), (1_i64 << (bits - 1)) - 1);
+        for val in (min..=max).step_by(step).chain([max]) {
+            let float = f32::from_int(val, bits, Scaling::Symmetric);
+            assert_eq!(float.to_int(bits, Scaling::Symmetric), val);
+        }
+    }
+
+    // Round trip through files for every policy.
+    let samples: Vec<i32> = [-8388608, -1, 0, 1, 8388607, 123456].iter().map(|x| x << 8).collect();
+    let source = WaveFile::from_samples_scaled(&samples, 1, 48000, WaveFormat::Pcm, 24, Scaling::Shift).unwrap();
+    for scaling in [Scaling::Asymmetric, Scaling::Symmetric, Scaling::Shift] {
+        let floats = source.samples_scaled::<f32>(scaling).unwrap();
+        let wave_file = WaveFile::from_samples_scaled(&floats, 1, 48000, WaveFormat::IeeeFloat, 32, scaling).unwrap();
+        let wave_file = WaveFile::from_samples_scaled(&wave_file.samples_scaled::<f32>(scaling).unwrap(), 1, 48000, WaveFormat::Pcm, 24, scaling).unwrap();
+        assert_eq!(wave_file.samples_scaled::<i32>(Scaling::Shift).unwrap(), samples);
+    }
+    let wave_file = WaveFile::from_samples(&[0x1234_i16, -2], 1, 48000, WaveFormat::Pcm, 16).unwrap();
+    assert_eq!(wave_file.samples_scaled::<i32>(Scaling::Shift).unwrap(), vec![0x12340000, -0x20000]);
+    assert!(matches!(wave_file.samples_scaled::<u8>(Scaling::Shift), Err(WaveDecodeError::IncompatibleSampleType { .. })));
+}
+
+#[test]
+fn integer_requantization() {
+    use crate::{WaveFile, WaveFormat};
+    use crate::util::math::{Rounding, Scaling};
+
+    // Widening is an exact shift and narrowing it again restores the original data.
+    let samples: Vec<i16> = vec![0x1234, -1, i16::MIN, i16::MAX];
+    let mut wave_file = WaveFile::from_samples(&samples, 2, 48000, WaveFormat::Pcm, 16).unwrap();
+    let original = wave_file.sample_data.clone();
+    wave_file.requantize(24, Rounding::Truncate).unwrap();
+    assert_eq!(wave_file.samples_scaled::<i32>(Scaling::Shift).unwrap(), vec![0x12340000, -0x10000, i32::MIN, 0x7fff0000]);
+    assert_eq!((wave_file.frame_size(), wave_file.data_rate), (6, 288000));
+    wave_file.requantize(32, Rounding::Truncate).unwrap();
+    assert_eq!(wave_file.samples::<i32>().unwrap(), vec![0x12340000, -0x10000, i32::MIN, 0x7fff0000]);
+    wave_file.requantize(16, Rounding::Nearest).unwrap();
+    assert_eq!(wave_file.sample_data, original);
+
+    let mut wave_file = WaveFile::from_samples(&[0_u8, 128, 255], 1, 8000, WaveFormat::Pcm, 8).unwrap();
+    wave_file.requantize(16, Rounding::Truncate).unwrap();
+    assert_eq!(wave_file.samples::<i16>().unwrap(), vec![-0x8000, 0, 0x7f00]);
+
+    // Narrowing truncates or rounds, rounding saturates at MAX.
+    let samples: Vec<i32> = [0x1234ff, 0x7fffff, -0x800000, 0x123480, -0x123481].iter().map(|x| x << 8).collect();
+    for (rounding, expected) in [(Rounding::Truncate, [0x1234, 0x7fff, -0x8000, 0x1234, -0x1235]), (Rounding::Nearest, [0x1235, 0x7fff, -0x8000, 0x1235, -0x1235])] {
+        let mut wave_file = WaveFile::from_samples_scaled(&samples, 1, 48000, WaveFormat::Pcm, 24, Scaling::Shift).unwrap();
+        wave_file.requantize(16, rounding).unwrap();
+        assert_eq!(wave_file.samples::<i16>().unwrap(), expected);
+    }
+
+    let mut wave_file = WaveFile::from_samples(&[0.5_f32], 1, 8000, WaveFormat::IeeeFloat, 32).unwrap();
+    assert!(matches!(wave_file.requantize(16, Rounding::Nearest), Err(WaveDecodeError::UnsupportedFormat(_))));
+}
+
+#[test]
+fn dithered_bit_depth_reduction() {
+    use crate::{WaveFile, WaveFormat};
+    use crate::dither::{Ditherer, DitherKind, NoiseShaping};
+    use crate::util::math::Rounding;
+
+    let source: Vec<f32> = (0..4000).map(|x| ((x as f32) * 0.01).sin() * 0.5).collect();
+    let reduce = |kind: DitherKind, shaping: NoiseShaping, seed: u64| {
+        let mut wave_file = WaveFile::from_samples(&source, 2, 48000, WaveFormat::IeeeFloat, 32).unwrap();
+        wave_file.reduce_bit_depth(16, &mut Ditherer::new(kind, shaping, seed)).unwrap();
+        assert_eq!((wave_file.format, wave_file.bits_per_sample, wave_file.frame_size()), (WaveFormat::Pcm, 16, 4));
+        wave_file.samples::<i16>().unwrap()
+    };
+
+    // Without dither the samples are rounded, dither stays within its amplitude and is reproducible.
+    let rounded = reduce(DitherKind::None, NoiseShaping::None, 0);
+    assert!(rounded.iter().zip(&source).all(|(x, y)| (*x as f32 - y * 32768.0).abs() <= 0.5));
+    for (kind, amplitude) in [(DitherKind::Rectangular, 1), (DitherKind::Triangular, 1), (DitherKind::HighPassTriangular, 1)] {
+        let dithered = reduce(kind, NoiseShaping::None, 7);
+        assert_eq!(dithered, reduce(kind, NoiseShaping::None, 7));
+        assert_ne!(dithered, reduce(kind, NoiseShaping::None, 8));
+        assert_ne!(dithered, rounded);
+        assert!(dithered.iter().zip(&rounded).all(|(x, y)| (x - y).abs() <= amplitude));
+    }
+
+    // Noise shaping keeps the signal, the error only moves to high frequencies.
+    for shaping in [NoiseShaping::FirstOrder, NoiseShaping::SecondOrder, NoiseShaping::Lipshitz] {
+        let shaped = reduce(DitherKind::Triangular, shaping, 1);
+        let error: f64 = shaped.iter().zip(&source).map(|(x, y)| *x as f64 - (*y as f64) * 32768.0).sum();
+        assert!(error.abs() / (shaped.len() as f64) < 0.1);
+        assert!(shaped.iter().zip(&rounded).all(|(x, y)| (x - y).abs() <= 16));
+    }
+
+    // Integer PCM is reduced the same way, without dither it equals rounding.
+    let samples: Vec<i32> = (0..1000).map(|x| (x * 4099 - 2000000) << 8).collect();
+    let mut dithered = WaveFile::from_samples(&samples, 1, 48000, WaveFormat::Pcm, 24).unwrap();
+    dithered.reduce_bit_depth(16, &mut Ditherer::new(DitherKind::None, NoiseShaping::None, 0)).unwrap();
+    let mut rounded = WaveFile::from_samples(&samples, 1, 48000, WaveFormat::Pcm, 24).unwrap();
+    rounded.requantize(16, Rounding::Nearest).unwrap();
+    assert_eq!(dithered.samples::<i16>().unwrap(), rounded.samples::<i16>().unwrap());
+
+    // The writer applies the same reduction while encoding and leaves the source untouched.
+    let wave_file = WaveFile::from_samples(&source, 2, 48000, WaveFormat::IeeeFloat, 32).unwrap();
+    let mut writer = WaveWriter::new(Cursor::new(Vec::new()));
+    writer.reduce_bit_depth(16, Ditherer::new(DitherKind::HighPassTriangular, NoiseShaping::None, 7));
+    writer.encode(&wave_file).unwrap();
+    let written = WaveReader::new(Cursor::new(writer.into_inner().into_inner())).decode().unwrap();
+    assert_eq!((written.format, written.bits_per_sample), (WaveFormat::Pcm, 16));
+    assert_eq!(written.samples::<i16>().unwrap(), reduce(DitherKind::HighPassTriangular, NoiseShaping::None, 7));
+    assert_eq!(wave_file.format, WaveFormat::IeeeFloat);
+}
+
+#[test]
+fn narrowing_reads() {
+    use crate::{Narrowing, SampleOptions, WaveFile, WaveFormat};
+    use crate::dither::DitherKind;
+    use crate::util::math::Scaling;
+
+    let samples: Vec<i32> = [0x1234ff, 0x7fffff, -0x800000, 0x123480, -0x123481, 0x40].iter().map(|x| x << 8).collect();
+    let wave_file = WaveFile::from_samples_scaled(&samples, 2, 48000, WaveFormat::Pcm, 24, Scaling::Shift).unwrap();
+    let read = |narrowing: Narrowing| wave_file.samples_with::<i16>(SampleOptions { scaling: Scaling::Shift, narrowing });
+
+    assert!(matches!(read(Narrowing::Refuse), Err(WaveDecodeError::IncompatibleSampleType { .. })));
+    assert_eq!(read(Narrowing::Truncate).unwrap(), vec![0x1234, 0x7fff, -0x8000, 0x1234, -0x1235, 0]);
+    assert_eq!(read(Narrowing::Round).unwrap(), vec![0x1235, 0x7fff, -0x8000, 0x1235, -0x1235, 0]);
+    let dithered = read(Narrowing::Dither(DitherKind::Triangular, 3)).unwrap();
+    assert_eq!(dithered, read(Narrowing::Dither(DitherKind::Triangular, 3)).unwrap());
+    assert!(dithered.iter().zip(read(Narrowing::Round).unwrap()).all(|(x, y)| (*x as i32 - y as i32).abs() <= 1));
+
+    // Asymmetric scaling maps MAX onto MAX, positive values are scaled down slightly more than by a shift.
+    let options = SampleOptions { narrowing: Narrowing::Round, ..SampleOptions::new() };
+    assert_eq!(wave_file.samples_with::<i16>(options).unwrap(), vec![0x1235, 0x7fff, -0x8000, 0x1234, -0x1235, 0]);
+
+    // Narrowing into u8 and from float, compatible types are not affected.
+    assert_eq!(wave_file.samples_with::<u8>(options).unwrap(), vec![0x92, 0xff, 0x00, 0x92, 0x6e, 0x80]);
+    assert_eq!(wave_file.samples_with::<i32>(SampleOptions { scaling: Scaling::Shift, ..options }).unwrap(), samples);
+    let floats = WaveFile::from_samples(&[-1.0_f32, -0.5, 0.5, 1.0, 2.0], 1, 48000, WaveFormat::IeeeFloat, 32).unwrap();
+    assert_eq!(floats.samples_with::<i16>(options).unwrap(), vec![-32768, -16384, 16384, 32767, 32767]);
+    let options = SampleOptions { scaling: Scaling::Symmetric, narrowing: Narrowing::Truncate };
+    assert_eq!(floats.samples_with::<i16>(options).unwrap(), vec![-32768, -16384, 16384, 32767, 32767]);
+    assert_eq!(floats.samples_with::<f32>(SampleOptions::new()).unwrap(), floats.samples::<f32>().unwrap());
+}
+
+#[test]
+fn extended_sample_types() {
+    use crate::{I24, Narrowing, Sample, SampleOptions, WaveFile, WaveFormat};
+    use crate::util::math::Scaling;
+
+    // Signed 8-bit samples are stored with the sign bit flipped.
+    let wave_file = WaveFile::from_samples(&[-128_i8, -1, 0, 127], 1, 8000, WaveFormat::Pcm, 8).unwrap();
+    assert_eq!(wave_file.sample_data, vec![0x00, 0x7f, 0x80, 0xff]);
+    assert_eq!(wave_file.samples::<i8>().unwrap(), vec![-128, -1, 0, 127]);
+    assert_eq!(wave_file.samples::<u8>().unwrap(), vec![0x00, 0x7f, 0x80, 0xff]);
+
+    // Unsigned 16-bit samples have their silence at 32768.
+    let samples: Vec<u16> = vec![0, 0x7fff, 0x8000, 0xffff];
+    let wave_file = WaveFile::from_samples(&samples, 2, 8000, WaveFormat::Pcm, 16).unwrap();
+    assert_eq!(wave_file.samples::<i16>().unwrap(), vec![-32768, -1, 0, 32767]);
+    assert_eq!(wave_file.samples::<u16>().unwrap(), samples);
+    assert_eq!(WaveFile::from_samples(&[0x80_u8], 1, 8000, WaveFormat::Pcm, 8).unwrap().samples::<u16>().unwrap(), vec![0x8000]);
+
+    // Packed 24-bit samples keep the layout of the file and can be borrowed.
+    let samples: Vec<I24> = [-8388608, -1, 0, 0x123456, 8388607].into_iter().map(I24::new).collect();
+    let wave_file = WaveFile::from_samples(&samples, 1, 48000, WaveFormat::Pcm, 24).unwrap();
+    assert_eq!(wave_file.sample_data[9..12], [ 0x56, 0x34, 0x12 ]);
+    assert_eq!(wave_file.samples::<I24>().unwrap(), samples);
+    assert!(matches!(wave_file.samples_view::<I24>().unwrap(), std::borrow::Cow::Borrowed(_)));
+    assert_eq!(wave_file.samples_scaled::<i32>(Scaling::Shift).unwrap(), vec![i32::MIN, -0x100, 0, 0x12345600, 0x7fffff00]);
+    let narrowed = WaveFile::from_samples(&samples, 1, 48000, WaveFormat::Pcm, 16).unwrap();
+    let expected: Vec<i16> = samples.iter().map(|x| i16::from_int(x.get() as i64, 24, Scaling::Asymmetric)).collect();
+    assert_eq!(narrowed.samples::<i16>().unwrap(), expected);
+    assert_eq!(expected, vec![i16::MIN, 0, 0, 0x1234, i16::MAX]);
+    assert_eq!((I24::new(i32::MAX), I24::new(i32::MIN)), (I24::MAX, I24::MIN));
+    assert_eq!(I24::from_int(0x1234, 16, Scaling::Shift).get(), 0x123400);
+
+    // u8 and i8 are read from wider PCM according to the narrowing policy.
+    let read = |wave_file: &WaveFile, narrowing: Narrowing| wave_file.samples_with::<u8>(SampleOptions { narrowing, ..SampleOptions::new() });
+    assert!(matches!(read(&wave_file, Narrowing::Refuse), Err(WaveDecodeError::IncompatibleSampleType { bits: 24, .. })));
+    assert_eq!(read(&wave_file, Narrowing::Truncate).unwrap(), vec![0x00, 0x7f, 0x80, 0x92, 0xff]);
+    assert_eq!(read(&wave_file, Narrowing::Round).unwrap(), vec![0x00, 0x80, 0x80, 0x92, 0xff]);
+    let wave_file = WaveFile::from_samples(&[-32768_i16, 0x1280, 32767], 1, 48000, WaveFormat::Pcm, 16).unwrap();
+    assert_eq!(read(&wave_file, Narrowing::Truncate).unwrap(), vec![0x00, 0x92, 0xff]);
+    assert_eq!(read(&wave_file, Narrowing::Round).unwrap(), vec![0x00, 0x92, 0xff]);
+    let options = SampleOptions { scaling: Scaling::Shift, narrowing: Narrowing::Round };
+    assert_eq!(wave_file.samples_with::<u8>(options).unwrap(), vec![0x00, 0x93, 0xff]);
+    assert_eq!(wave_file.samples_with::<i8>(options).unwrap(), vec![-128, 19, 127]);
+    assert!(matches!(wave_file.samples::<i8>(), Err(WaveDecodeError::IncompatibleSampleType { bits: 16, .. })));
+}
@@ -1,6 +1,8 @@
 
 pub mod read;
+pub mod write;
 
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum Endian {
     Little,
     Big,
@@ -1,6 +1,7 @@
-use std::io::{Read, Cursor, Error};
+use std::io::{Read, Cursor, Error, ErrorKind};
 
 use crate::util::exts::{Endian, Endian::*};
+use crate::util::text::{decode_text, TextEncoding};
 
 /**
  * The `ReadExt` trait extends implementing structs etc. by methods to directly read
@@ -15,6 +16,7 @@ pub trait SizedDataRead: Read + Sized {
     fn read_u64(&mut self, endian: Endian) -> Result<u64, Error>;
     fn read_u128(&mut self, endian: Endian) -> Result<u128, Error>;
 
+    fn read_i8(&mut self) -> Result<i8, Error>;
     fn read_i16(&mut self, endian: Endian) -> Result<i16, Error>;
     fn read_i24(&mut self, endian: Endian) -> Result<i32, Error>;
     fn read_i32(&mut self, endian: Endian) -> Result<i32, Error>;
@@ -23,7 +25,8 @@ pub trait SizedDataRead: Read + Sized {
     fn read_f32(&mut self, endian: Endian) -> Result<f32, Error>;
     fn read_f64(&mut self, endian: Endian) -> Result<f64, Error>;
 
-    fn read_string(&mut self, len: u32) -> Result<String, Error>;
+    fn read_bytes(&mut self, len: u32) -> Result<Vec<u8>, Error>;
+    fn read_string(&mut self, len: u32, fallback: TextEncoding) -> Result<String, Error>;
 }
 
 impl<R: Read> SizedDataRead for R {
@@ -91,6 +94,14 @@ impl<R: Read> SizedDataRead for R {
         }
     }
 
+    fn read_i8(&mut self) -> Result<i8, Error> {
+        let mut buf: [u8; 1] = [ 0 ];
+        match self.read_exact(&mut buf) {
+            Ok(()) => Ok( i8::from_le_bytes(buf) ),
+            Err(e) => Err(e),
+        }
+    }
+
     fn read_i16(&mut self, endian: Endian) -> Result<i16, Error> {
         let mut buf: [u8; 2] = [0; 2];
         match self.read_exact(&mut buf) {
@@ -169,13 +180,23 @@ impl<R: Read> SizedDataRead for R {
         }
     }
 
-    fn read_string(&mut self, len: u32) -> Result<String, Error> {
-        let mut str: String = String::new();
-        match self.take(len as u64).read_to_string(&mut str) {
-            Ok(_) => Ok(str),
-            Err(e) => Err(e),
+    fn read_bytes(&mut self, len: u32) -> Result<Vec<u8>, Error> {
+        // The length is not preallocated, it is taken from the source and may exceed it by far.
+        let mut buf: Vec<u8> = Vec::new();
+        self.take(len as u64).read_to_end(&mut buf)?;
+        match buf.len() == (len as usize) {
+            true => Ok(buf),
+            false => Err(Error::new(ErrorKind::UnexpectedEof, "failed to fill whole buffer")),
         }
     }
+
+    /**
+     * Reads a string of `len` bytes. The string ends at the first NUL character,
+     * bytes that are not valid UTF-8 are decoded with `fallback`.
+     */
+    fn read_string(&mut self, len: u32, fallback: TextEncoding) -> Result<String, Error> {
+        Ok( decode_text(&self.read_bytes(len)?, fallback) )
+    }
 }
 
 pub trait CursorExt: Read + Sized {
@@ -0,0 +1,115 @@
+use std::io::{Write, Error};
+
+use crate::util::exts::{Endian, Endian::*};
+
+/**
+ * The `SizedDataWrite` trait is the counterpart to `SizedDataRead` and extends implementing
+ * structs etc. by methods to directly write signed or unsigned numerics with a given byteorder.
+ */
+pub trait SizedDataWrite: Write + Sized {
+
+    fn write_u8(&mut self, val: u8) -> Result<(), Error>;
+    fn write_u16(&mut self, val: u16, endian: Endian) -> Result<(), Error>;
+    fn write_u24(&mut self, val: u32, endian: Endian) -> Result<(), Error>;
+    fn write_u32(&mut self, val: u32, endian: Endian) -> Result<(), Error>;
+    fn write_u64(&mut self, val: u64, endian: Endian) -> Result<(), Error>;
+    fn write_u128(&mut self, val: u128, endian: Endian) -> Result<(), Error>;
+
+    fn write_i8(&mut self, val: i8) -> Result<(), Error>;
+    fn write_i16(&mut self, val: i16, endian: Endian) -> Result<(), Error>;
+    fn write_i24(&mut self, val: i32, endian: Endian) -> Result<(), Error>;
+    fn write_i32(&mut self, val: i32, endian: Endian) -> Result<(), Error>;
+    fn write_i64(&mut self, val: i64, endian: Endian) -> Result<(), Error>;
+
+    fn write_f32(&mut self, val: f32, endian: Endian) -> Result<(), Error>;
+    fn write_f64(&mut self, val: f64, endian: Endian) -> Result<(), Error>;
+}
+
+impl<W: Write> SizedDataWrite for W {
+
+    fn write_u8(&mut self, val: u8) -> Result<(), Error> {
+        self.write_all(&[ val ])
+    }
+
+    fn write_u16(&mut self, val: u16, endian: Endian) -> Result<(), Error> {
+        match endian {
+            Little => self.write_all(&val.to_le_bytes()),
+            Big => self.write_all(&val.to_be_bytes()),
+        }
+    }
+
+    fn write_u24(&mut self, val: u32, endian: Endian) -> Result<(), Error> {
+        match endian {
+            Little => self.write_all(&val.to_le_bytes()[0..3]),
+            Big => self.write_all(&val.to_be_bytes()[1..4]),
+        }
+    }
+
+    fn write_u32(&mut self, val: u32, endian: Endian) -> Result<(), Error> {
+        match endian {
+            Little => self.write_all(&val.to_le_bytes()),
+            Big => self.write_all(&val.to_be_bytes()),
+        }
+    }
+
+    fn write_u64(&mut self, val: u64, endian: Endian) -> Result<(), Error> {
+        match endian {
+            Little => self.write_all(&val.to_le_bytes()),
+            Big => self.write_all(&val.to_be_bytes()),
+        }
+    }
+
+    fn write_u128(&mut self, val: u128, endian: Endian) -> Result<(), Error> {
+        match endian {
+            Little => self.write_all(&val.to_le_bytes()),
+            Big => self.write_all(&val.to_be_bytes()),
+        }
+    }
+
+    fn write_i8(&mut self, val: i8) -> Result<(), Error> {
+        self.write_all(&val.to_le_bytes())
+    }
+
+    fn write_i16(&mut self, val: i16, endian: Endian) -> Result<(), Error> {
+        match endian {
+            Little => self.write_all(&val.to_le_bytes()),
+            Big => self.write_all(&val.to_be_bytes()),
+        }
+    }
+
+    fn write_i24(&mut self, val: i32, endian: Endian) -> Result<(), Error> {
+        // The upper byte only carries the sign extension, dropping it keeps the two's complement intact.
+        match endian {
+            Little => self.write_all(&val.to_le_bytes()[0..3]),
+            Big => self.write_all(&val.to_be_bytes()[1..4]),
+        }
+    }
+
+    fn write_i32(&mut self, val: i32, endian: Endian) -> Result<(), Error> {
+        match endian {
+            Little => self.write_all(&val.to_le_bytes()),
+            Big => self.write_all(&val.to_be_bytes()),
+        }
+    }
+
+    fn write_i64(&mut self, val: i64, endian: Endian) -> Result<(), Error> {
+        match endian {
+            Little => self.write_all(&val.to_le_bytes()),
+            Big => self.write_all(&val.to_be_bytes()),
+        }
+    }
+
+    fn write_f32(&mut self, val: f32, endian: Endian) -> Result<(), Error> {
+        match endian {
+            Little => self.write_all(&val.to_le_bytes()),
+            Big => self.write_all(&val.to_be_bytes()),
+        }
+    }
+
+    fn write_f64(&mut self, val: f64, endian: Endian) -> Result<(), Error> {
+        match endian {
+            Little => self.write_all(&val.to_le_bytes()),
+            Big => self.write_all(&val.to_be_bytes()),
+        }
+    }
+}
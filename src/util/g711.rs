@@ -0,0 +1,30 @@
+/*
+ * Expansion of G.711 companded samples to 16-bit linear PCM, as used by the A-law and µ-law
+ * formats of WAVE and AU.
+ */
+
+pub fn expand_mulaw(val: u8) -> i16 {
+    let val = !val;
+    let exponent = (val >> 4) & 0x07;
+    let mantissa = (val & 0x0f) as i16;
+    let magnitude = (((mantissa << 3) + 0x84) << exponent) - 0x84;
+    match val & 0x80 {
+        0 => magnitude,
+        _ => -magnitude,
+    }
+}
+
+pub fn expand_alaw(val: u8) -> i16 {
+    let val = val ^ 0x55;
+    let exponent = (val >> 4) & 0x07;
+    let mantissa = (val & 0x0f) as i16;
+    let magnitude = match exponent {
+        0 => (mantissa << 4) + 0x08,
+        e => ((mantissa << 4) + 0x108) << (e - 1),
+    };
+    /* In contrast to µ-law a set sign bit denotes a positive value */
+    match val & 0x80 {
+        0 => -magnitude,
+        _ => magnitude,
+    }
+}
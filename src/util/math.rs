@@ -1,27 +1,19 @@
+/*
+ * The mappings below use asymmetric scaling: negative values are scaled by |MIN| and positive
+ * values by MAX, so the limits of both types are mapped onto each other. Integers are mapped
+ * with exact integer arithmetic and rounded to the nearest value.
+ */
 
 pub fn map_u8_to_i16(val: u8) -> i16 {
-    let moved = (val as i16) - 128_i16;
-    if moved < 0 {
-        (((moved as f32) / (i8::MIN as f32)) * (i16::MIN as f32)) as i16
-    } else {
-        (((moved as f32) / (i8::MAX as f32)) * (i16::MAX as f32)) as i16
-    }
+    scale_asymmetric((val as i64) - 128, 8, 16) as i16
 }
 
 pub fn map_u8_to_i32(val: u8) -> i32 {
-    let moved = (val as i32) - 128_i32;
-    match moved < 0 {
-        true => (((moved as f64) / (i8::MIN as f64)) * (i32::MIN as f64)) as i32,
-        false => (((moved as f64) / (i8::MAX as f64)) * (i32::MAX as f64)) as i32
-    }
+    scale_asymmetric((val as i64) - 128, 8, 32) as i32
 }
 
 pub fn map_u8_to_i64(val: u8) -> i64 {
-    let moved = (val as i64) - 128_i64;
-    match moved < 0 {
-        true => (((moved as f64) / (i8::MIN as f64)) * (i64::MIN as f64)) as i64,
-        false => (((moved as f64) / (i8::MAX as f64)) * (i64::MAX as f64)) as i64
-    }
+    scale_asymmetric((val as i64) - 128, 8, 64)
 }
 
 pub fn map_u8_to_f32(val: u8) -> f32 {
@@ -41,17 +33,11 @@ pub fn map_u8_to_f64(val: u8) -> f64 {
 }
 
 pub fn map_i16_to_i32(val: i16) -> i32 {
-    match val < 0 {
-        true => (((val as f32) / (i16::MIN as f32)) * (i32::MIN as f32)) as i32,
-        false => (((val as f32) / (i16::MAX as f32)) * (i32::MAX as f32)) as i32
-    }
+    scale_asymmetric(val as i64, 16, 32) as i32
 }
 
 pub fn map_i16_to_i64(val: i16) -> i64 {
-    match val < 0 {
-        true => (((val as f32) / (i16::MIN as f32)) * (i64::MIN as f32)) as i64,
-        false => (((val as f32) / (i16::MAX as f32)) * (i64::MAX as f32)) as i64
-    }
+    scale_asymmetric(val as i64, 16, 64)
 }
 
 pub fn map_i16_to_f32(val: i16) -> f32 {
@@ -69,21 +55,11 @@ pub fn map_i16_to_f64(val: i16) -> f64 {
 }
 
 pub fn map_i24_to_i32(val: i32) -> i32 {
-    let i24_max = 8388607;
-    let i24_min = -8388608;
-    match val < 0 {
-        true => (((val as f64) / (i24_min as f64)) * (i32::MIN as f64)) as i32,
-        false => (((val as f64) / (i24_max as f64)) * (i32::MAX as f64)) as i32
-    }
+    scale_asymmetric(val as i64, 24, 32) as i32
 }
 
 pub fn map_i24_to_i64(val: i32) -> i64 {
-    let i24_max = 8388607;
-    let i24_min = -8388608;
-    match val < 0 {
-        true => (((val as f32) / (i24_min as f32)) * (i64::MIN as f32)) as i64,
-        false => (((val as f32) / (i24_max as f32)) * (i64::MAX as f32)) as i64
-    }
+    scale_asymmetric(val as i64, 24, 64)
 }
 
 pub fn map_i24_to_f32(val: i32) -> f32 {
@@ -105,10 +81,7 @@ pub fn map_i24_to_f64(val: i32) -> f64 {
 }
 
 pub fn map_i32_to_i64(val: i32) -> i64 {
-    match val < 0 {
-        true => (((val as f32) / (i32::MIN as f32)) * (i64::MIN as f32)) as i64,
-        false => (((val as f32) / (i32::MAX as f32)) * (i64::MAX as f32)) as i64
-    }
+    scale_asymmetric(val as i64, 32, 64)
 }
 
 pub fn map_i32_to_f32(val: i32) -> f32 {
@@ -137,4 +110,160 @@ pub fn map_i64_to_f64(val: i64) -> f64 {
         true => (val as f64) / (i64::MIN as f64).abs(),
         false => (val as f64) / (i64::MAX as f64)
     }
-}
\ No newline at end of file
+}
+/*
+ * Mappings from normalized samples to integer PCM, the counterpart of the functions above.
+ * Values are rounded to the nearest integer and clipped to [-1.0, 1.0], NaN is mapped to silence.
+ */
+
+pub fn map_f64_to_u8(val: f64) -> u8 {
+    let val = val.clamp(-1.0, 1.0);
+    let moved = match val < 0.0 {
+        true => (val * (i8::MIN as f64).abs()).round() as i32,
+        false => (val * (i8::MAX as f64)).round() as i32
+    };
+    (moved + 128) as u8
+}
+
+pub fn map_f64_to_i16(val: f64) -> i16 {
+    let val = val.clamp(-1.0, 1.0);
+    match val < 0.0 {
+        true => (val * (i16::MIN as f64).abs()).round() as i16,
+        false => (val * (i16::MAX as f64)).round() as i16
+    }
+}
+
+pub fn map_f64_to_i24(val: f64) -> i32 {
+    let i24_max = 8388607;
+    let i24_min = -8388608;
+    let val = val.clamp(-1.0, 1.0);
+    match val < 0.0 {
+        true => (val * (i24_min as f64).abs()).round() as i32,
+        false => (val * (i24_max as f64)).round() as i32
+    }
+}
+
+pub fn map_f64_to_i32(val: f64) -> i32 {
+    let val = val.clamp(-1.0, 1.0);
+    match val < 0.0 {
+        true => (val * (i32::MIN as f64).abs()).round() as i32,
+        false => (val * (i32::MAX as f64)).round() as i32
+    }
+}
+
+pub fn map_f64_to_i64(val: f64) -> i64 {
+    let val = val.clamp(-1.0, 1.0);
+    match val < 0.0 {
+        true => (val * (i64::MIN as f64).abs()).round() as i64,
+        false => (val * (i64::MAX as f64)).round() as i64
+    }
+}
+
+/**
+ * Policy for converting between integer PCM of different widths and floating point samples.
+ */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Scaling {
+    Asymmetric,             /* Negative values are scaled by |MIN|, positive values by MAX, so both limits map onto -1.0 and 1.0 */
+    Symmetric,              /* Scaled by 2^(n-1), MAX maps slightly below 1.0. Integers of up to 24 bits round-trip through f32 losslessly */
+    Shift,                  /* Integers are widened and narrowed by bit shifts, floats are scaled like Symmetric */
+}
+
+/**
+ * Scales `val` of `from` bits to `to` bits such that MIN maps onto MIN and MAX onto MAX.
+ */
+fn scale_asymmetric(val: i64, from: u16, to: u16) -> i64 {
+    let (num, den) = match val < 0 {
+        true => (1_i128 << (to - 1), 1_i128 << (from - 1)),
+        false => ((1_i128 << (to - 1)) - 1, (1_i128 << (from - 1)) - 1),
+    };
+    let val = (val as i128) * num;
+
+    // Rounds half away from zero
+    (match val < 0 {
+        true => (val - den / 2) / den,
+        false => (val + den / 2) / den,
+    }) as i64
+}
+
+#[inline(always)]
+pub(crate) fn int_limits(bits: u16) -> (i64, i64) {
+    let max = ((1_u64 << (bits - 1)) - 1) as i64;
+    (-max - 1, max)
+}
+
+/**
+ * Converts an integer sample of `from` bits into a sample of `to` bits. Narrowing by Shift
+ * truncates towards negative infinity, the other policies round to the nearest value.
+ */
+pub fn map_int_to_int(val: i64, from: u16, to: u16, scaling: Scaling) -> i64 {
+    match (scaling, to < from) {
+        (Scaling::Asymmetric, _) => scale_asymmetric(val, from, to),
+        (Scaling::Symmetric, true) => narrow_int(val, from, to, Rounding::Nearest),
+        (Scaling::Shift, true) => narrow_int(val, from, to, Rounding::Truncate),
+        (_, false) => widen_int(val, from, to),
+    }
+}
+
+/**
+ * How the bits that are dropped when narrowing integer samples are handled.
+ */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Rounding {
+    Truncate,               /* Dropped bits are discarded, i.e. rounds towards negative infinity */
+    Nearest,                /* Rounds half up, values that would exceed MAX saturate */
+}
+
+/**
+ * Widens an integer sample of `from` bits to `to` bits by shifting it left, this is lossless.
+ */
+#[inline(always)]
+pub fn widen_int(val: i64, from: u16, to: u16) -> i64 {
+    val << (to - from)
+}
+
+/**
+ * Narrows an integer sample of `from` bits to `to` bits by shifting it right.
+ */
+#[inline(always)]
+pub fn narrow_int(val: i64, from: u16, to: u16, rounding: Rounding) -> i64 {
+    let shift = from - to;
+    match rounding {
+        Rounding::Truncate => val >> shift,
+        Rounding::Nearest => {
+            let (min, max) = int_limits(to);
+            (((val as i128 + (1_i128 << shift >> 1)) >> shift) as i64).clamp(min, max)
+        },
+    }
+}
+
+/**
+ * Converts an integer sample of `bits` bits into a float sample in [-1.0, 1.0].
+ */
+pub fn map_int_to_f64(val: i64, bits: u16, scaling: Scaling) -> f64 {
+    let (min, max) = int_limits(bits);
+    match scaling {
+        Scaling::Asymmetric => match val < 0 {
+            true => (val as f64) / (min as f64).abs(),
+            false => (val as f64) / (max as f64),
+        },
+        Scaling::Symmetric | Scaling::Shift => (val as f64) / (min as f64).abs(),
+    }
+}
+
+/**
+ * Converts a float sample into an integer sample of `bits` bits. The value is rounded to the
+ * nearest integer and clipped, NaN is mapped to silence.
+ */
+pub fn map_f64_to_int(val: f64, bits: u16, scaling: Scaling) -> i64 {
+    let (min, max) = int_limits(bits);
+    let scaled = match scaling {
+        Scaling::Asymmetric => match val < 0.0 {
+            true => val.max(-1.0) * (min as f64).abs(),
+            false => val.min(1.0) * (max as f64),
+        },
+        Scaling::Symmetric | Scaling::Shift => val * (min as f64).abs(),
+    };
+    // Casting saturates and maps NaN to 0
+    (scaled.round() as i64).clamp(min, max)
+}
@@ -1,3 +1,4 @@
-
 pub mod exts;
-pub mod math;
\ No newline at end of file
+pub mod g711;
+pub mod math;
+pub mod text;
@@ -0,0 +1,134 @@
+use std::borrow::Cow;
+
+use encoding_rs::Encoding;
+
+/**
+ * Character encoding of text metadata. RIFF files predate UTF-8, therefore a lot of files
+ * contain text in a legacy codepage, which is either declared in a CSET chunk or not at all.
+ */
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub enum TextEncoding {
+    Utf8,
+    Latin1,                 /* ISO-8859-1 */
+    Windows1252,
+    ShiftJis,
+    CodePage(u16),          /* Any other Windows codepage number, e.g. 1251 or 936 */
+}
+
+impl TextEncoding {
+
+    /**
+     * Maps a Windows codepage number as used in the CSET chunk to an encoding.
+     */
+    pub fn from_code_page(code_page: u16) -> TextEncoding {
+        match code_page {
+            65001 => TextEncoding::Utf8,
+            28591 => TextEncoding::Latin1,
+            1252 => TextEncoding::Windows1252,
+            932 => TextEncoding::ShiftJis,
+            x => TextEncoding::CodePage(x),
+        }
+    }
+
+    /**
+     * Decodes `bytes` with this encoding. The text ends at the first NUL character, as
+     * RIFF strings are NUL-terminated and often padded with additional NULs.
+     * Bytes that cannot be decoded are replaced by U+FFFD, this function never fails.
+     */
+    pub fn decode(&self, bytes: &[u8]) -> String {
+        let bytes = strip_nul(bytes);
+        match self.normalized() {
+            TextEncoding::Utf8 => String::from_utf8_lossy(bytes).into_owned(),
+            TextEncoding::Latin1 => bytes.iter().map(|b| *b as char).collect(),
+            x => match x.encoding() {
+                Some(encoding) => encoding.decode_without_bom_handling(bytes).0.into_owned(),
+                None => String::from_utf8_lossy(bytes).into_owned(),
+            }
+        }
+    }
+
+    /**
+     * Encodes `text` with this encoding, characters that cannot be represented are replaced.
+     */
+    pub fn encode<'a>(&self, text: &'a str) -> Cow<'a, [u8]> {
+        match self.normalized() {
+            TextEncoding::Utf8 => Cow::Borrowed(text.as_bytes()),
+            TextEncoding::Latin1 => Cow::Owned(text.chars()
+                .map(|c| match (c as u32) < 0x100 {
+                    true => c as u8,
+                    false => b'?',
+                })
+                .collect()),
+            x => match x.encoding() {
+                Some(encoding) => encoding.encode(text).0,
+                None => Cow::Borrowed(text.as_bytes()),
+            }
+        }
+    }
+
+    fn normalized(&self) -> TextEncoding {
+        match self {
+            TextEncoding::CodePage(x) => TextEncoding::from_code_page(*x),
+            x => *x,
+        }
+    }
+
+    fn encoding(&self) -> Option<&'static Encoding> {
+        match self {
+            TextEncoding::Utf8 => Some(encoding_rs::UTF_8),
+            TextEncoding::Latin1 => None,
+            TextEncoding::Windows1252 => Some(encoding_rs::WINDOWS_1252),
+            TextEncoding::ShiftJis => Some(encoding_rs::SHIFT_JIS),
+            TextEncoding::CodePage(x) => match x {
+                866 => Some(encoding_rs::IBM866),
+                874 => Some(encoding_rs::WINDOWS_874),
+                936 => Some(encoding_rs::GBK),
+                949 => Some(encoding_rs::EUC_KR),
+                950 => Some(encoding_rs::BIG5),
+                1250 => Some(encoding_rs::WINDOWS_1250),
+                1251 => Some(encoding_rs::WINDOWS_1251),
+                1253 => Some(encoding_rs::WINDOWS_1253),
+                1254 => Some(encoding_rs::WINDOWS_1254),
+                1255 => Some(encoding_rs::WINDOWS_1255),
+                1256 => Some(encoding_rs::WINDOWS_1256),
+                1257 => Some(encoding_rs::WINDOWS_1257),
+                1258 => Some(encoding_rs::WINDOWS_1258),
+                10000 => Some(encoding_rs::MACINTOSH),
+                20866 => Some(encoding_rs::KOI8_R),
+                21866 => Some(encoding_rs::KOI8_U),
+                28592 => Some(encoding_rs::ISO_8859_2),
+                28593 => Some(encoding_rs::ISO_8859_3),
+                28594 => Some(encoding_rs::ISO_8859_4),
+                28595 => Some(encoding_rs::ISO_8859_5),
+                28596 => Some(encoding_rs::ISO_8859_6),
+                28597 => Some(encoding_rs::ISO_8859_7),
+                28598 => Some(encoding_rs::ISO_8859_8),
+                28603 => Some(encoding_rs::ISO_8859_13),
+                28605 => Some(encoding_rs::ISO_8859_15),
+                51932 => Some(encoding_rs::EUC_JP),
+                54936 => Some(encoding_rs::GB18030),
+                _ => None,
+            },
+        }
+    }
+}
+
+/**
+ * Decodes text metadata: valid UTF-8 is taken as is, otherwise `fallback` is used.
+ */
+pub fn decode_text(bytes: &[u8], fallback: TextEncoding) -> String {
+    match std::str::from_utf8(strip_nul(bytes)) {
+        Ok(x) => x.to_string(),
+        Err(_) => fallback.decode(bytes),
+    }
+}
+
+/**
+ * Cuts `bytes` at the first NUL character.
+ */
+pub fn strip_nul(bytes: &[u8]) -> &[u8] {
+    match bytes.iter().position(|b| *b == 0) {
+        Some(x) => &bytes[..x],
+        None => bytes,
+    }
+}
@@ -0,0 +1,277 @@
+use crate::{DecodeWarning, FourCC, WaveDecodeError, WaveFile, WaveFormat};
+use crate::read::*;
+use crate::write::is_typed_chunk;
+
+use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::io::{Cursor, Read};
+use std::mem::discriminant;
+
+#[derive(Clone, Copy, Debug, PartialEq, PartialOrd)]
+pub enum Severity {
+    Info,                   /* Not a violation, e.g. chunks that are preserved but not checked */
+    Warning,                /* Violates the specification, but is handled by most readers */
+    Error,                  /* Likely breaks other readers or hides parts of the file */
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum IssueKind {
+    /* data_rate of the fmt chunk is not sample_rate * frame_size */
+    DataRateMismatch { declared: u32, expected: u64 },
+    /* frame_size of the fmt chunk is not channels * bytes per sample */
+    FrameSizeMismatch { declared: u16, expected: u32 },
+    /* A chunk of odd size is not followed by a pad byte */
+    MissingPadByte { id: FourCC },
+    /* A chunk starts at an odd offset */
+    OddAlignedChunk { id: FourCC },
+    /* Sample count of the fact chunk does not match the size of the data chunk */
+    FactMismatch { declared: u32, actual: u32 },
+    /* Number of bits set in the channel mask does not match the number of channels */
+    ChannelMaskMismatch { channels: u16, mask_channels: u32 },
+    /* A chunk that may only be present once was found again */
+    DuplicateChunk { id: FourCC },
+    /* Bytes behind the last chunk or behind the end stated in the RIFF header */
+    TrailingGarbage { len: u64 },
+    /* Size in the RIFF header exceeds the size of the source */
+    RiffSizeMismatch { declared: u64, actual: u64 },
+    /* A chunk exceeds the source */
+    TruncatedChunk { id: FourCC },
+    /* A chunk that is not interpreted by the reader, its contents are not checked */
+    UnknownChunk { id: FourCC },
+    /* A LIST chunk of a type other than INFO, its contents are not checked */
+    UnknownListType { list_type: FourCC },
+    /* The data chunk states more bytes than available, only `actual` bytes are used */
+    DataClamped { declared: u32, actual: u32 },
+    /* A mandatory chunk is missing */
+    MissingChunk { id: FourCC },
+    /* The file could not be decoded, further checks were skipped */
+    Undecodable(WaveDecodeError),
+}
+
+/**
+ * A single finding of the validator, `offset` points to the header of the affected chunk.
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValidationIssue {
+    pub severity: Severity,
+    pub offset: Option<u64>,
+    pub kind: IssueKind,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ValidationReport {
+    pub issues: Vec<ValidationIssue>,
+}
+
+impl ValidationReport {
+
+    /**
+     * Returns true if no issue with severity `Error` was found.
+     */
+    pub fn is_valid(&self) -> bool {
+        !self.issues.iter().any(|x| x.severity == Severity::Error)
+    }
+
+    fn push(&mut self, severity: Severity, offset: Option<u64>, kind: IssueKind) {
+        self.issues.push(ValidationIssue { severity, offset, kind });
+    }
+}
+
+impl Display for ValidationIssue {
+
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        let severity = match self.severity {
+            Severity::Info => "info",
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        };
+        match self.offset {
+            Some(x) => write!(f, "{} at {}: ", severity, x)?,
+            None => write!(f, "{}: ", severity)?,
+        };
+        match &self.kind {
+            IssueKind::DataRateMismatch { declared, expected } => write!(f, "data rate is {} B/s, expected {} B/s", declared, expected),
+            IssueKind::FrameSizeMismatch { declared, expected } => write!(f, "frame size is {} B, expected {} B", declared, expected),
+            IssueKind::MissingPadByte { id } => write!(f, "chunk '{}' of odd size is not followed by a pad byte", id),
+            IssueKind::OddAlignedChunk { id } => write!(f, "chunk '{}' starts at an odd offset", id),
+            IssueKind::FactMismatch { declared, actual } => write!(f, "fact chunk states {} samples, data chunk holds {}", declared, actual),
+            IssueKind::ChannelMaskMismatch { channels, mask_channels } =>
+                write!(f, "channel mask assigns {} channels, file has {}", mask_channels, channels),
+            IssueKind::DuplicateChunk { id } => write!(f, "chunk '{}' is present more than once", id),
+            IssueKind::TrailingGarbage { len } => write!(f, "{} trailing bytes behind the last chunk", len),
+            IssueKind::RiffSizeMismatch { declared, actual } => write!(f, "RIFF header states {} B, source has {} B", declared, actual),
+            IssueKind::TruncatedChunk { id } => write!(f, "chunk '{}' exceeds the source", id),
+            IssueKind::UnknownChunk { id } => write!(f, "chunk '{}' is not interpreted", id),
+            IssueKind::UnknownListType { list_type } => write!(f, "LIST chunk of type '{}' is not interpreted", list_type),
+            IssueKind::DataClamped { declared, actual } => write!(f, "data chunk states {} B, only {} B are available", declared, actual),
+            IssueKind::MissingChunk { id } => write!(f, "mandatory chunk '{}' is missing", id),
+            IssueKind::Undecodable(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+/**
+ * Checks a RIFF WAVE file against the specification. Only a source that is not a RIFF WAVE
+ * file at all is an error, everything else is reported as issue.
+ */
+pub fn validate<R: Read>(mut source: R) -> Result<ValidationReport, WaveDecodeError> {
+    let mut buf: Vec<u8> = Vec::new();
+    source.read_to_end(&mut buf)?;
+    if buf.len() < 12 || read_u32(&buf, 0) != RIFF_MAGIC || read_u32(&buf, 8) != WAVE_MAGIC {
+        return Err(WaveDecodeError::InvalidHeader);
+    }
+
+    let mut report = ValidationReport::default();
+    let chunks = check_layout(&buf, &mut report);
+
+    let wave_file = match WaveReader::with_options(Cursor::new(buf), DecodeOptions::lenient()).decode() {
+        Ok(x) => x,
+        Err(e) => {
+            report.push(Severity::Error, None, IssueKind::Undecodable(e));
+            return Ok(report);
+        },
+    };
+    check_format(&wave_file, &chunks, &mut report);
+    check_warnings(&wave_file, &chunks, &mut report);
+
+    Ok(report)
+}
+
+/**
+ * Walks the chunk headers and checks alignment, pad bytes, duplicates and trailing bytes.
+ * Returns id, offset and size of every chunk.
+ */
+fn check_layout(buf: &[u8], report: &mut ValidationReport) -> Vec<(FourCC, u64, u64)> {
+    let declared = (read_u32(buf, 4) as u64) + 8;
+    let actual = buf.len() as u64;
+    let end = match declared > actual {
+        true => {
+            report.push(Severity::Error, Some(0), IssueKind::RiffSizeMismatch { declared, actual });
+            actual
+        },
+        false => declared,
+    };
+
+    let mut chunks: Vec<(FourCC, u64, u64)> = Vec::new();
+    let mut pos: u64 = 12;
+    while pos < end {
+        if end - pos < 8 || !is_plausible_id(&buf[(pos as usize)..(pos as usize + 4)]) {
+            report.push(Severity::Warning, Some(pos), IssueKind::TrailingGarbage { len: end - pos });
+            break;
+        }
+        let id = FourCC::from_u32(read_u32(buf, pos as usize));
+        let size = read_u32(buf, pos as usize + 4) as u64;
+
+        if pos % 2 == 1 {
+            report.push(Severity::Error, Some(pos), IssueKind::OddAlignedChunk { id });
+        }
+        if chunks.iter().any(|(x, _, _)| *x == id) && is_typed_chunk(id.as_u32()) {
+            let severity = match id.as_u32() {
+                CHUNK_FMT_ | CHUNK_DATA => Severity::Error,
+                _ => Severity::Warning,
+            };
+            report.push(severity, Some(pos), IssueKind::DuplicateChunk { id });
+        }
+        chunks.push((id, pos, size));
+
+        let chunk_end = pos + 8 + size;
+        if chunk_end > end {
+            report.push(Severity::Error, Some(pos), IssueKind::TruncatedChunk { id });
+            break;
+        }
+
+        pos = chunk_end;
+        if size % 2 == 1 {
+            // A missing pad byte is detected by the next chunk header starting right behind the payload.
+            let padded = buf.get((pos as usize + 1)..(pos as usize + 5)).map(is_plausible_id).unwrap_or(false);
+            let unpadded = buf.get((pos as usize)..(pos as usize + 4)).map(is_plausible_id).unwrap_or(false);
+            match pos == end || (unpadded && !padded) {
+                true => report.push(Severity::Warning, Some(pos - 8 - size), IssueKind::MissingPadByte { id }),
+                false => pos += 1,
+            };
+        }
+    }
+
+    if end < actual {
+        report.push(Severity::Warning, Some(end), IssueKind::TrailingGarbage { len: actual - end });
+    }
+
+    chunks
+}
+
+/**
+ * Cross-checks the fields of the fmt and fact chunk.
+ */
+fn check_format(wave_file: &WaveFile, chunks: &[(FourCC, u64, u64)], report: &mut ValidationReport) {
+    let find = |id: u32| chunks.iter().find(|(x, _, _)| x.as_u32() == id);
+    let fmt_offset = find(CHUNK_FMT_).map(|(_, x, _)| *x);
+
+    // Frame size and data rate are only defined by the channel layout for uncompressed formats.
+    let uncompressed = matches!(wave_file.format, WaveFormat::Pcm | WaveFormat::IeeeFloat | WaveFormat::Alaw | WaveFormat::Mulaw | WaveFormat::WaveExt);
+    if uncompressed {
+        let expected = (wave_file.channels as u32) * (wave_file.bits_per_sample as u32).div_ceil(8);
+        if (wave_file.frame_size() as u32) != expected {
+            report.push(Severity::Error, fmt_offset, IssueKind::FrameSizeMismatch { declared: wave_file.frame_size(), expected });
+        }
+        let expected = (wave_file.sample_rate as u64) * (wave_file.frame_size() as u64);
+        if (wave_file.data_rate as u64) != expected {
+            report.push(Severity::Warning, fmt_offset, IssueKind::DataRateMismatch { declared: wave_file.data_rate, expected });
+        }
+    }
+
+    if let (Some(declared), Some((_, offset, _)), Some((_, _, size))) = (wave_file.num_of_samples, find(CHUNK_FACT), find(CHUNK_DATA)) {
+        if uncompressed && wave_file.frame_size() != 0 {
+            // The size is taken from the chunk header, the reader may not reach misaligned data chunks.
+            let actual = (size / (wave_file.frame_size() as u64)) as u32;
+            if declared != actual {
+                report.push(Severity::Warning, Some(*offset), IssueKind::FactMismatch { declared, actual });
+            }
+        }
+    }
+
+    if let Some(mask) = wave_file.channel_mask {
+        if mask != 0 && mask.count_ones() != (wave_file.channels as u32) {
+            report.push(Severity::Warning, fmt_offset, IssueKind::ChannelMaskMismatch { channels: wave_file.channels, mask_channels: mask.count_ones() });
+        }
+    }
+}
+
+/**
+ * Adds the issues that only the reader detects. Issues of the chunk structure that were
+ * already found by `check_layout` are not reported twice, neither are issues of chunks the
+ * reader only saw because it lost track of the chunk boundaries.
+ */
+fn check_warnings(wave_file: &WaveFile, chunks: &[(FourCC, u64, u64)], report: &mut ValidationReport) {
+    for warning in &wave_file.warnings {
+        let (severity, kind) = match *warning {
+            DecodeWarning::UnknownChunk { id, .. } => (Severity::Info, IssueKind::UnknownChunk { id }),
+            DecodeWarning::UnknownListType { list_type, .. } => (Severity::Info, IssueKind::UnknownListType { list_type }),
+            DecodeWarning::TrailingBytes { len, .. } => (Severity::Warning, IssueKind::TrailingGarbage { len }),
+            DecodeWarning::SizeMismatch { declared, actual } => match declared > actual {
+                true => (Severity::Error, IssueKind::RiffSizeMismatch { declared, actual }),
+                false => (Severity::Warning, IssueKind::RiffSizeMismatch { declared, actual }),
+            },
+            DecodeWarning::DataClamped { declared, actual, .. } => (Severity::Warning, IssueKind::DataClamped { declared, actual }),
+            DecodeWarning::TruncatedChunk { id, .. } => (Severity::Error, IssueKind::TruncatedChunk { id }),
+            DecodeWarning::MissingChunk(id) => (Severity::Error, IssueKind::MissingChunk { id }),
+        };
+
+        let covered = match kind {
+            IssueKind::TrailingGarbage { .. } | IssueKind::RiffSizeMismatch { .. } => report.issues.iter()
+                .any(|x| matches!(x.kind, IssueKind::TrailingGarbage { .. } | IssueKind::RiffSizeMismatch { .. })),
+            IssueKind::TruncatedChunk { .. } => report.issues.iter().any(|x| discriminant(&x.kind) == discriminant(&kind)),
+            _ => false,
+        };
+        let misread = match (&kind, warning.offset()) {
+            (IssueKind::TrailingGarbage { .. }, _) | (_, None) => false,
+            (_, Some(offset)) => !chunks.iter().any(|(_, x, _)| *x == offset),
+        };
+        if covered || misread {
+            continue;
+        }
+        report.push(severity, warning.offset(), kind);
+    }
+}
+
+fn read_u32(buf: &[u8], pos: usize) -> u32 {
+    u32::from_le_bytes([ buf[pos], buf[pos + 1], buf[pos + 2], buf[pos + 3] ])
+}
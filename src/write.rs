@@ -0,0 +1,225 @@
+use crate::{FourCC, WaveFile, WaveFormat};
+use crate::chunks::{Chunk, cue::write_cue_chunk, info::write_info_list};
+use crate::dither::Ditherer;
+use crate::read::*;
+use crate::util::exts::{Endian, write::*};
+use crate::util::text::TextEncoding;
+
+use std::io::{Error as IoError, ErrorKind, Write};
+
+/**
+ * Encodes a `WaveFile` as RIFF WAVE. Chunks keep the order of the decoded file, chunks that
+ * are interpreted by this library are written from their typed representation, all others
+ * are written untouched.
+ */
+pub struct WaveWriter<W>
+where W: Write {
+    sink: W,
+    reduction: Option<(u16, Ditherer)>,     /* Bit depth the samples are reduced to while encoding */
+}
+
+impl<W> WaveWriter<W>
+where W: Write {
+
+    pub fn new(sink: W) -> WaveWriter<W> {
+        WaveWriter::<W> { sink, reduction: None }
+    }
+
+    /**
+     * Reduces integer PCM of more than `bits` bits and IEEE float to `bits` bits while encoding,
+     * see `WaveFile::reduce_bit_depth`. The encoded `WaveFile` itself is not modified. The state
+     * of `ditherer` carries over from one encoded file to the next.
+     */
+    pub fn reduce_bit_depth(&mut self, bits: u16, ditherer: Ditherer) {
+        self.reduction = Some((bits, ditherer));
+    }
+
+    pub fn into_inner(self) -> W {
+        self.sink
+    }
+
+    pub fn encode(&mut self, wave_file: &WaveFile) -> Result<(), IoError> {
+        let reduced: WaveFile;
+        let wave_file = match self.reduction.as_mut() {
+            Some((bits, ditherer)) if wave_file.format == WaveFormat::IeeeFloat || wave_file.bits_per_sample > *bits => {
+                let mut copy = wave_file.clone();
+                copy.reduce_bit_depth(*bits, ditherer).map_err(|e| IoError::new(ErrorKind::InvalidInput, e.to_string()))?;
+                reduced = copy;
+                &reduced
+            },
+            _ => wave_file,
+        };
+        let chunks = encode_chunks(wave_file)?;
+
+        let riff_size = chunks.iter()
+            .map(|x| match x {
+                EncodedChunk::Data => 8 + padded(wave_file.sample_data.len() as u64),
+                EncodedChunk::Bytes(bytes) => bytes.len() as u64,
+            })
+            .sum::<u64>() + 4;
+        if riff_size > (u32::MAX as u64) {
+            return Err(IoError::new(ErrorKind::InvalidInput, "WAVE file exceeds 4 GiB"));
+        }
+
+        self.sink.write_u32(RIFF_MAGIC, Endian::Little)?;
+        self.sink.write_u32(riff_size as u32, Endian::Little)?;
+        self.sink.write_u32(WAVE_MAGIC, Endian::Little)?;
+        for chunk in &chunks {
+            match chunk {
+                EncodedChunk::Data => write_raw_chunk(&mut self.sink, FourCC::from_u32(CHUNK_DATA), &wave_file.sample_data)?,
+                EncodedChunk::Bytes(bytes) => self.sink.write_all(bytes)?,
+            };
+        }
+
+        self.sink.flush()
+    }
+}
+
+pub(crate) enum EncodedChunk {
+    Data,                           /* Sample data is written directly from the WaveFile to avoid a copy */
+    Bytes(Vec<u8>),
+}
+
+/**
+ * Returns true if the chunk is written from the typed fields of `WaveFile` instead of its raw payload.
+ */
+pub(crate) fn is_typed_chunk(id: u32) -> bool {
+    matches!(id, CHUNK_FMT_ | CHUNK_FACT | CHUNK_DATA | CHUNK_CSET | CHUNK_BEXT | CHUNK_CUE_ | CHUNK_INST | CHUNK_ACID | CHUNK_IXML | CHUNK_AXML)
+}
+
+/**
+ * Serializes all chunks of `wave_file` except sample data in the order they should be written.
+ */
+pub(crate) fn encode_chunks(wave_file: &WaveFile) -> Result<Vec<EncodedChunk>, IoError> {
+    let mut order: Vec<(u32, Option<&Chunk>)> = wave_file.chunks.iter().map(|x| (x.id.as_u32(), Some(x))).collect();
+    if !order.iter().any(|(id, _)| *id == CHUNK_FMT_) {
+        order.insert(0, (CHUNK_FMT_, None));
+    }
+    if !order.iter().any(|(id, _)| *id == CHUNK_DATA) {
+        order.push((CHUNK_DATA, None));
+    }
+
+    // Typed chunks that are set but were not part of the decoded file are placed in front of the data chunk.
+    let optional: [(u32, bool); 9] = [
+        (CHUNK_FACT, wave_file.format != WaveFormat::Pcm || wave_file.num_of_samples.is_some()),
+        (CHUNK_BEXT, wave_file.bext.is_some()),
+        (CHUNK_CSET, wave_file.charset.is_some()),
+        (CHUNK_LIST, !wave_file.metadata.info_entries().is_empty()),
+        (CHUNK_INST, wave_file.instrument.is_some()),
+        (CHUNK_ACID, wave_file.acid.is_some()),
+        (CHUNK_IXML, wave_file.ixml.is_some()),
+        (CHUNK_AXML, wave_file.axml.is_some()),
+        (CHUNK_CUE_, !wave_file.cue_points.is_empty()),
+    ];
+    for (id, present) in optional {
+        if present && !order.iter().any(|(x, raw)| *x == id && (id != CHUNK_LIST || is_info_list(*raw))) {
+            let data_pos = order.iter().position(|(x, _)| *x == CHUNK_DATA).unwrap_or(order.len());
+            order.insert(data_pos, (id, None));
+        }
+    }
+
+    let encoding = wave_file.charset
+        .and_then(|x| x.encoding())
+        .unwrap_or(TextEncoding::Utf8);
+
+    let mut chunks: Vec<EncodedChunk> = Vec::new();
+    let mut written: Vec<u32> = Vec::new();
+    for (id, raw) in order {
+        // Typed chunks are written only once, a LIST chunk is only typed if it is an INFO list.
+        let typed = is_typed_chunk(id) || (id == CHUNK_LIST && is_info_list(raw));
+        if typed {
+            if written.contains(&id) {
+                continue;
+            }
+            written.push(id);
+        }
+
+        let mut bytes: Vec<u8> = Vec::new();
+        match (id, raw) {
+            (CHUNK_DATA, _) => {
+                chunks.push(EncodedChunk::Data);
+                continue;
+            },
+            (CHUNK_FMT_, _) => write_fmt_chunk(&mut bytes, wave_file)?,
+            (CHUNK_FACT, _) => write_fact_chunk(&mut bytes, wave_file)?,
+            (CHUNK_CSET, _) => if let Some(x) = wave_file.charset { x.write(&mut bytes)? },
+            (CHUNK_BEXT, _) => if let Some(x) = &wave_file.bext { x.write(&mut bytes)? },
+            (CHUNK_CUE_, _) => if !wave_file.cue_points.is_empty() { write_cue_chunk(&mut bytes, &wave_file.cue_points)? },
+            (CHUNK_INST, _) => if let Some(x) = wave_file.instrument { x.write(&mut bytes)? },
+            (CHUNK_ACID, _) => if let Some(x) = wave_file.acid { x.write(&mut bytes)? },
+            (CHUNK_IXML, _) => if let Some(x) = &wave_file.ixml { write_raw_chunk(&mut bytes, FourCC::from_u32(id), x.as_bytes())? },
+            (CHUNK_AXML, _) => if let Some(x) = &wave_file.axml { write_raw_chunk(&mut bytes, FourCC::from_u32(id), x.as_bytes())? },
+            (CHUNK_LIST, _) if typed => write_info_list(&mut bytes, &wave_file.metadata, encoding)?,
+            (_, Some(x)) => write_raw_chunk(&mut bytes, x.id, x.data.bytes())?,
+            (_, None) => (),
+        };
+        if !bytes.is_empty() {
+            chunks.push(EncodedChunk::Bytes(bytes));
+        }
+    }
+
+    Ok(chunks)
+}
+
+fn is_info_list(raw: Option<&Chunk>) -> bool {
+    match raw {
+        Some(x) => x.data.bytes().get(0..4) == Some(&CHUNK_LIST_SUB_INFO.to_le_bytes()[..]),
+        None => true,
+    }
+}
+
+#[inline(always)]
+fn padded(size: u64) -> u64 {
+    size + size % 2
+}
+
+fn write_fmt_chunk<S: SizedDataWrite>(sink: &mut S, wave_file: &WaveFile) -> Result<(), IoError> {
+    let size: u32 = match wave_file.format {
+        WaveFormat::Pcm => 16,
+        WaveFormat::WaveExt => 40,
+        _ => 18,
+    };
+
+    sink.write_u32(CHUNK_FMT_, Endian::Little)?;
+    sink.write_u32(size, Endian::Little)?;
+    sink.write_u16(wave_file.format as u16, Endian::Little)?;
+    sink.write_u16(wave_file.channels, Endian::Little)?;
+    sink.write_u32(wave_file.sample_rate, Endian::Little)?;
+    sink.write_u32(wave_file.data_rate, Endian::Little)?;
+    sink.write_u16(wave_file.frame_size, Endian::Little)?;
+    sink.write_u16(wave_file.bits_per_sample, Endian::Little)?;
+    match size {
+        40 => {
+            sink.write_u16(22, Endian::Little)?;
+            sink.write_u16(wave_file.valid_bps.unwrap_or(wave_file.bits_per_sample), Endian::Little)?;
+            sink.write_u32(wave_file.channel_mask.unwrap_or(0), Endian::Little)?;
+            sink.write_u128(wave_file.sub_format.unwrap_or(0), Endian::Little)
+        },
+        18 => sink.write_u16(0, Endian::Little),
+        _ => Ok(()),
+    }
+}
+
+fn write_fact_chunk<S: SizedDataWrite>(sink: &mut S, wave_file: &WaveFile) -> Result<(), IoError> {
+    let frames = match wave_file.frame_size {
+        0 => 0,
+        x => (wave_file.sample_data.len() / (x as usize)) as u32,
+    };
+
+    sink.write_u32(CHUNK_FACT, Endian::Little)?;
+    sink.write_u32(4, Endian::Little)?;
+    sink.write_u32(wave_file.num_of_samples.unwrap_or(frames), Endian::Little)
+}
+
+/**
+ * Writes a chunk with header and pad byte.
+ */
+pub(crate) fn write_raw_chunk<S: SizedDataWrite>(sink: &mut S, id: FourCC, data: &[u8]) -> Result<(), IoError> {
+    sink.write_u32(id.as_u32(), Endian::Little)?;
+    sink.write_u32(data.len() as u32, Endian::Little)?;
+    sink.write_all(data)?;
+    if data.len() % 2 == 1 {
+        sink.write_u8(0)?;
+    }
+    Ok(())
+}